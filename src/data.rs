@@ -21,19 +21,22 @@ pub struct TraitInfo {
     pub safety: syn::Unsafety,
     pub generics: syn::Generics,
     pub generic_bounds: Vec<syn::TyParamBound>,
-    pub items: Vec<syn::TraitItem>
+    pub items: Vec<syn::TraitItem>,
+    pub async_methods: Vec<syn::Ident>
 }
 
 impl TraitInfo {
     pub fn new(safety: syn::Unsafety,
                generics: syn::Generics,
                generic_bounds: Vec<syn::TyParamBound>,
-               items: Vec<syn::TraitItem>) -> TraitInfo {
+               items: Vec<syn::TraitItem>,
+               async_methods: Vec<syn::Ident>) -> TraitInfo {
         TraitInfo {
-            safety: safety,
-            generics: generics,
-            generic_bounds: generic_bounds,
-            items: items
+            safety,
+            generics,
+            generic_bounds,
+            items,
+            async_methods
         }
     }
 }
@@ -49,7 +52,24 @@ lazy_static! {
 pub struct RequestedMock {
     pub traits: Vec<syn::Path>,
     pub attributes: Vec<syn::Attribute>,
-    pub maybe_type_name: Option<syn::Ident>
+    pub maybe_type_name: Option<syn::Ident>,
+    pub is_nice: bool,
+    /// If the most recently added matching given behaviour is selected instead of the first one, see `#[prefer_latest]`.
+    pub prefers_latest: bool,
+    /// The methods stated with `with_impl` which are called if no given behaviour matches.
+    pub fake_methods: Vec<syn::ImplItem>,
+    /// The type of the real object wrapped by the mock, see `#[spy_on(Type)]`.
+    pub spied_type: Option<syn::Ident>,
+    pub fixture: Option<Fixture>
+}
+
+/// A JSON file of the recorded calls of a mock relative to the crate's manifest directory.
+#[derive(Debug,PartialEq,Clone)]
+pub enum Fixture {
+    /// The calls of the mock and their return values are recorded to the file, see `#[record_to("path")]`.
+    Record(String),
+    /// The calls are answered by the return values recorded in the file, see `#[replay_from("path")]`.
+    Replay(String)
 }
 lazy_static! {
     pub static ref REQUESTED_MOCKS: Mutex<Vec<RequestedMock>> = {
@@ -63,9 +83,19 @@ pub struct Binding {
     pub fields: Vec<BindingField>
 }
 
+impl Binding {
+    /// Checks if the type of some bound value is inferred from its initializer.
+    pub fn has_inferred_types(&self) -> bool {
+        self.fields.iter().any(|field| field.ty.is_none())
+    }
+}
+
 pub struct BindingField {
+    /// If the value is bound with `bind mut` and is wrapped in a `RefCell`.
+    pub is_mut: bool,
     pub name: syn::Ident,
-    pub ty: syn::Ty,
+    /// The type of the bound value, `None` if the type is inferred.
+    pub ty: Option<syn::Ty>,
     pub initializer: syn::Expr
 }
 
@@ -77,19 +107,150 @@ lazy_static! {
 }
 
 
-#[derive(Debug,Clone)]
+#[derive(Debug,PartialEq,Clone)]
 pub enum BehaviourMatcher {
     Void,
     Explicit(syn::Expr),
     PerArgument(Vec<syn::Expr>)
 }
 
+impl BehaviourMatcher {
+    /// Renders the matcher as in the statement, using `repr` for the individual matcher expressions.
+    pub fn repr_with<F: FnMut(&syn::Expr) -> String>(&self, mut repr: F) -> String {
+        match *self {
+            BehaviourMatcher::Void => String::new(),
+            BehaviourMatcher::Explicit(ref expr) => format!(" {} ", repr(expr)),
+            BehaviourMatcher::PerArgument(ref exprs) => format!("({})", exprs.iter().map(repr).collect::<Vec<_>>().join(", "))
+        }
+    }
+
+    /// The matcher expressions of the statement.
+    pub fn exprs(&self) -> Vec<&syn::Expr> {
+        match *self {
+            BehaviourMatcher::Void => Vec::new(),
+            BehaviourMatcher::Explicit(ref expr) => vec![expr],
+            BehaviourMatcher::PerArgument(ref exprs) => exprs.iter().collect()
+        }
+    }
+}
+
 #[derive(Debug,PartialEq,Clone)]
 pub enum Return {
     FromValue(syn::Expr),
     FromCall(syn::Expr),
+    FromIter(syn::Expr),
     FromSpy,
-    Panic
+    Panic,
+    /// Panics with the message, i.e., a value implementing `Display`.
+    PanicWith(syn::Expr),
+    /// Panics with the value as payload which can be inspected by `catch_unwind`.
+    PanicWithPayload(syn::Expr),
+    AfterTrigger(Box<Return>, syn::Expr),
+    /// Returns `Err` with the error converted by `From::from`.
+    FailWith(syn::Expr),
+    /// Returns `Err` with the error converted by `From::from` for the calls selected by the schedule, and the inner value otherwise.
+    InjectFaults(Box<Return>, FaultSchedule, syn::Expr),
+    /// Delays the call before returning the inner value, or `()` if there is none.
+    Delayed(Option<Box<Return>>, Delay)
+}
+
+/// Delays a call matching a given behaviour, see `Return::Delayed`.
+#[derive(Debug,PartialEq,Clone)]
+pub enum Delay {
+    /// Sleeps for the `Duration`.
+    Sleep(syn::Expr),
+    /// Waits for the barrier, e.g., a `std::sync::Barrier`.
+    BlockUntil(syn::Expr)
+}
+
+impl ::std::fmt::Display for Delay {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        match *self {
+            Delay::Sleep(ref duration) => write!(f, "then_sleep {}", quote!(#duration)),
+            Delay::BlockUntil(ref barrier) => write!(f, "then_block_until {}", quote!(#barrier))
+        }
+    }
+}
+
+impl Return {
+    /// The schedule of the injected faults, if any.
+    pub fn fault_schedule(&self) -> Option<&FaultSchedule> {
+        match *self {
+            Return::InjectFaults(_, ref schedule, _) => Some(schedule),
+            Return::Delayed(Some(ref inner), _) => inner.fault_schedule(),
+            _ => None
+        }
+    }
+
+    /// The expressions stated in the then-part of the statement.
+    pub fn exprs(&self) -> Vec<&syn::Expr> {
+        match *self {
+            Return::FromValue(ref expr) | Return::FromCall(ref expr) | Return::FromIter(ref expr)
+            | Return::PanicWith(ref expr) | Return::PanicWithPayload(ref expr) | Return::FailWith(ref expr) => vec![expr],
+            Return::FromSpy | Return::Panic => Vec::new(),
+            Return::AfterTrigger(ref inner, ref trigger) => {
+                let mut exprs = inner.exprs();
+                exprs.push(trigger);
+                exprs
+            },
+            Return::InjectFaults(ref inner, ref schedule, ref error) => {
+                let mut exprs = inner.exprs();
+                match *schedule {
+                    FaultSchedule::After(ref expr) | FaultSchedule::OnCalls(ref expr) => exprs.push(expr),
+                    FaultSchedule::Randomly(ref fraction, ref seed) => exprs.extend(vec![fraction, seed])
+                }
+                exprs.push(error);
+                exprs
+            },
+            Return::Delayed(ref inner, ref delay) => {
+                let mut exprs = inner.as_ref().map_or(Vec::new(), |inner| inner.exprs());
+                match *delay {
+                    Delay::Sleep(ref expr) | Delay::BlockUntil(ref expr) => exprs.push(expr)
+                }
+                exprs
+            }
+        }
+    }
+}
+
+/// Selects the calls matching a given behaviour which fail, see `Return::InjectFaults`.
+#[derive(Debug,PartialEq,Clone)]
+pub enum FaultSchedule {
+    /// All calls after the first `N` ones fail.
+    After(syn::Expr),
+    /// The calls with the stated (1-based) numbers fail.
+    OnCalls(syn::Expr),
+    /// A fraction of the calls fails as determined by a seeded pseudo-random sequence.
+    Randomly(syn::Expr, syn::Expr)
+}
+
+impl ::std::fmt::Display for FaultSchedule {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        match *self {
+            FaultSchedule::After(ref expr) => write!(f, "then_fail_after {}", quote!(#expr)),
+            FaultSchedule::OnCalls(ref expr) => write!(f, "then_fail_on {}", quote!(#expr)),
+            FaultSchedule::Randomly(ref fraction, ref seed) => write!(f, "then_fail_randomly {} seed {}", quote!(#fraction), quote!(#seed))
+        }
+    }
+}
+
+impl ::std::fmt::Display for Return {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        match *self {
+            Return::FromValue(ref expr) => write!(f, "then_return {}", quote!(#expr)),
+            Return::FromCall(ref expr) => write!(f, "then_return_from {}", quote!(#expr)),
+            Return::FromIter(ref expr) => write!(f, "then_return_iter {}", quote!(#expr)),
+            Return::FromSpy => write!(f, "then_spy_on_object"),
+            Return::Panic => write!(f, "then_panic"),
+            Return::PanicWith(ref expr) => write!(f, "then_panic_with {}", quote!(#expr)),
+            Return::PanicWithPayload(ref expr) => write!(f, "then_panic_with_payload {}", quote!(#expr)),
+            Return::AfterTrigger(ref inner, ref trigger) => write!(f, "{} after_trigger {}", inner, quote!(#trigger)),
+            Return::FailWith(ref expr) => write!(f, "then_fail_with {}", quote!(#expr)),
+            Return::InjectFaults(ref inner, ref schedule, ref error) => write!(f, "{} {} with {}", inner, schedule, quote!(#error)),
+            Return::Delayed(Some(ref inner), ref delay) => write!(f, "{} {}", inner, delay),
+            Return::Delayed(None, ref delay) => write!(f, "{}", delay)
+        }
+    }
 }
 
 #[derive(Debug,PartialEq,Clone)]
@@ -108,6 +269,11 @@ pub struct GivenStatement {
     pub matcher: BehaviourMatcher,
     pub return_stmt: Return,
     pub repeat: GivenRepeat,
+    pub expect_repeat: Option<ExpectRepeat>,
+    /// Behaviours with a higher priority are matched first, see `with_priority`.
+    pub priority: Option<syn::Expr>,
+    /// If the matchers and the return value are evaluated by a closure capturing the environment of the `given!` block.
+    pub captures: bool,
 }
 
 impl GivenStatement {
@@ -120,37 +286,58 @@ impl GivenStatement {
         let method = &self.method;
         quote!(#method).to_string()
     }
+
+    /// The expectation of a combined given-and-expect statement sharing the statement's id and binding.
+    pub fn expect_statement(&self) -> Option<ExpectStatement> {
+        self.expect_repeat.as_ref().map(|repeat| ExpectStatement {
+            block_id: self.block_id,
+            stmt_id: self.stmt_id,
+            mock_var: self.mock_var.clone(),
+            ufc_trait: self.ufc_trait.clone(),
+            method: self.method.clone(),
+            matcher: self.matcher.clone(),
+            repeat: repeat.clone(),
+            captures: self.captures
+        })
+    }
 }
 
-impl ::std::fmt::Display for GivenStatement {
-    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
-        let match_expr = match &self.matcher {
-            &BehaviourMatcher::Void => String::new(),
-            &BehaviourMatcher::Explicit(ref expr) => format!(" {} ", quote!(#expr)),
-            &BehaviourMatcher::PerArgument(ref exprs) => format!("({})", exprs.iter().map(|e| quote!(#e).to_string()).collect::<Vec<_>>().join(", "))
-        };
-        let return_expr = match &self.return_stmt {
-            &Return::FromValue(ref expr) => format!("then_return {}", quote!(#expr)),
-            &Return::FromCall(ref expr) => format!("then_return_from {}", quote!(#expr)),
-            &Return::FromSpy => panic!("Return::FromSpy is not supported yet"),
-            &Return::Panic => String::from("then_panic")
-        };
+impl GivenStatement {
+    /// Renders the statement using `repr` for the individual matcher expressions.
+    pub fn repr_with<F: FnMut(&syn::Expr) -> String>(&self, repr: F) -> String {
         let repeat_expr = match &self.repeat {
             &GivenRepeat::Times(ref expr) => format!("times {}", quote!(#expr)),
             &GivenRepeat::Always => String::from("always")
         };
 
+        let expect_expr = match self.expect_repeat {
+            Some(ref expect_repeat) => format!(" expect {}", expect_repeat),
+            None => String::new()
+        };
+        let priority_expr = match self.priority {
+            Some(ref priority) => format!(" with_priority {}", quote!(#priority)),
+            None => String::new()
+        };
+
         let ufc_trait = &self.ufc_trait;
-        write!(f, "{}::{}{} {} {}",
-               quote!(#ufc_trait),
-               self.method,
-               match_expr,
-               return_expr,
-               repeat_expr
+        format!("{}::{}{} {} {}{}{}",
+                quote!(#ufc_trait),
+                self.method,
+                self.matcher.repr_with(repr),
+                self.return_stmt,
+                repeat_expr,
+                expect_expr,
+                priority_expr
         )
     }
 }
 
+impl ::std::fmt::Display for GivenStatement {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        write!(f, "{}", self.repr_with(|expr| quote!(#expr).to_string()))
+    }
+}
+
 pub type GivenStatements = HashMap<syn::Path, Vec<GivenStatement>>;
 lazy_static! {
     pub static ref GIVEN_STATEMENTS: Mutex<GivenStatements> = {
@@ -167,6 +354,17 @@ pub enum ExpectRepeat {
     Between(syn::Expr, syn::Expr),
 }
 
+impl ::std::fmt::Display for ExpectRepeat {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        match *self {
+            ExpectRepeat::Times(ref expr) => write!(f, "times {}", quote!(#expr)),
+            ExpectRepeat::AtLeast(ref expr) => write!(f, "at_least {}", quote!(#expr)),
+            ExpectRepeat::AtMost(ref expr) => write!(f, "at_most {}", quote!(#expr)),
+            ExpectRepeat::Between(ref lb, ref ub) => write!(f, "between {}, {}", quote!(#lb), quote!(#ub)),
+        }
+    }
+}
+
 #[derive(Debug,Clone)]
 pub struct ExpectStatement {
     pub block_id: usize,
@@ -175,7 +373,9 @@ pub struct ExpectStatement {
     pub ufc_trait: syn::Path,
     pub method: syn::Ident,
     pub matcher: BehaviourMatcher,
-    pub repeat: ExpectRepeat
+    pub repeat: ExpectRepeat,
+    /// If the matchers are evaluated by a closure capturing the environment of the `expect_interactions!` block.
+    pub captures: bool
 }
 
 impl ExpectStatement {
@@ -190,31 +390,30 @@ impl ExpectStatement {
     }
 }
 
-impl ::std::fmt::Display for ExpectStatement {
-    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
-        let match_expr = match &self.matcher {
-            &BehaviourMatcher::Void => String::new(),
-            &BehaviourMatcher::Explicit(ref expr) => format!(" {} ", quote!(#expr)),
-            &BehaviourMatcher::PerArgument(ref exprs) => format!("({})", exprs.iter().map(|e| quote!(#e).to_string()).collect::<Vec<_>>().join(", "))
-        };
-        let repeat_expr = match &self.repeat {
-            &ExpectRepeat::Times(ref expr) => format!("times {}", quote!(#expr)),
-            &ExpectRepeat::AtLeast(ref expr) => format!("at_least {}", quote!(#expr)),
-            &ExpectRepeat::AtMost(ref expr) => format!("at_most {}", quote!(#expr)),
-            &ExpectRepeat::Between(ref lb, ref ub) => format!("at_least {}, {}", quote!(#lb), quote!(#ub)),
-        };
-
+impl ExpectStatement {
+    /// Renders the statement using `repr` for the individual matcher expressions.
+    pub fn repr_with<F: FnMut(&syn::Expr) -> String>(&self, repr: F) -> String {
         let ufc_trait = &self.ufc_trait;
-        write!(f, "{}::{}{} {}",
-               quote!(#ufc_trait),
-               self.method,
-               match_expr,
-               repeat_expr
+        format!("{}::{}{} {}",
+                quote!(#ufc_trait),
+                self.method,
+                self.matcher.repr_with(repr),
+                self.repeat
         )
     }
 }
 
+impl ::std::fmt::Display for ExpectStatement {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        write!(f, "{}", self.repr_with(|expr| quote!(#expr).to_string()))
+    }
+}
+
 pub type ExpectStatements = HashMap<syn::Path, Vec<ExpectStatement>>;
+
+/// The ids of the statements of a block grouped by the mock variable they refer to.
+pub type StmtIdsPerMock = Vec<(syn::Ident, Vec<usize>)>;
+
 lazy_static! {
     pub static ref EXPECT_STATEMENTS: Mutex<ExpectStatements> = {
         Mutex::new(HashMap::new())
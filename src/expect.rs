@@ -14,16 +14,19 @@
  */
 use syn;
 use syn::parse::*;
+use quote;
 
 use data::*;
+use given::parse_bind;
 use generate::binding_implementer::implement_initialize_binding;
+use generate::{implement_create_behaviour_guard, implement_statement_fn, can_capture};
 
-named!(pub parse_bind -> BindingField,
-    do_parse!(
-        punct!("bind") >> name: call!(syn::parse::ident) >>
-        punct!(":") >> ty: call!(syn::parse::ty) >>
-        punct!("=") >> initializer: call!(syn::parse::expr) >>
-        (BindingField { name, ty, initializer })
+named!(pub parse_expect_repeat -> ExpectRepeat,
+    alt!( preceded!(keyword!("times"), syn::parse::expr) => { ExpectRepeat::Times }
+        | preceded!(keyword!("at_least"), syn::parse::expr) => { ExpectRepeat::AtLeast }
+        | preceded!(keyword!("at_most"), syn::parse::expr) => { ExpectRepeat::AtMost }
+        | preceded!(keyword!("between"), tuple!( call!(syn::parse::expr), preceded!(punct!(","), syn::parse::expr) )) => { |(e1, e2)| ExpectRepeat::Between(e1, e2) }
+        | keyword!("never") => { |_| ExpectRepeat::Times(syn::parse::expr("0").expect("")) }
     )
 );
 
@@ -37,14 +40,9 @@ named!(pub parse_expect_interaction -> ExpectStatement,
                         if es.is_empty() { BehaviourMatcher::Void }
                         else { BehaviourMatcher::PerArgument(es) }
               }
-            | call!(syn::parse::expr) => { |e| BehaviourMatcher::Explicit(e) }
-        ) >>
-        repeat: alt!( preceded!(keyword!("times"), syn::parse::expr) => { |e| ExpectRepeat::Times(e) }
-                    | preceded!(keyword!("at_least"), syn::parse::expr) => { |e| ExpectRepeat::AtLeast(e) }
-                    | preceded!(keyword!("at_most"), syn::parse::expr) => { |e| ExpectRepeat::AtMost(e) }
-                    | preceded!(keyword!("between"), tuple!( call!(syn::parse::expr), preceded!(punct!(","), syn::parse::expr) )) => { |(e1, e2)| ExpectRepeat::Between(e1, e2) }
-                    | keyword!("never") => { |_| ExpectRepeat::Times(syn::parse::expr("0").expect("")) }
+            | call!(syn::parse::expr) => { BehaviourMatcher::Explicit }
         ) >>
+        repeat: parse_expect_repeat >>
         (ExpectStatement {
             block_id: 0,
             stmt_id: 0,
@@ -52,17 +50,29 @@ named!(pub parse_expect_interaction -> ExpectStatement,
             ufc_trait,
             method,
             matcher: args,
-            repeat
+            repeat,
+            captures: false
         })
     )
 );
 
+named!(expect_block_content -> (Vec<BindingField>, Vec<ExpectStatement>),
+    tuple!(
+        terminated_list!(punct!(";"), parse_bind),
+        terminated_list!(punct!(";"), parse_expect_interaction)
+    )
+);
+
 named!(pub parse_expect_interactions -> (Vec<BindingField>, Vec<ExpectStatement>),
     delimited!(tuple!(keyword!("expect_interactions"), punct!("!"), punct!("{")),
-               tuple!(
-                   terminated_list!(punct!(";"), parse_bind),
-                   terminated_list!(punct!(";"), parse_expect_interaction)
-               ),
+               expect_block_content,
+               punct!("}")
+    )
+);
+
+named!(pub parse_scoped_expect_interactions -> (Vec<BindingField>, Vec<ExpectStatement>),
+    delimited!(tuple!(keyword!("expect_interactions_scoped"), punct!("!"), punct!("{")),
+               expect_block_content,
                punct!("}")
     )
 );
@@ -70,44 +80,110 @@ named!(pub parse_expect_interactions -> (Vec<BindingField>, Vec<ExpectStatement>
 
 pub fn handle_expect_interactions(source: &str, absolute_position: usize) -> (String, String) {
     if let IResult::Done(remainder, (binding_fields, expect_definitions)) = parse_expect_interactions(source) {
-        let mut statements = acquire!(EXPECT_STATEMENTS);
-
-        let mut add_statements = Vec::new();
-        for (idx, mut stmt) in expect_definitions.into_iter().enumerate() {
-            stmt.block_id = absolute_position;
-            stmt.stmt_id = absolute_position + idx;
-            let stmt_id = stmt.stmt_id;
-
-            {
-                let mock_var = &stmt.mock_var;
-                let ufc_trait_name = stmt.trait_name();
-                let method_name = stmt.method_name();
-
-                let stmt_repr = format!("{}", stmt);
-                add_statements.push(match &stmt.repeat {
-                    &ExpectRepeat::Times(ref expr) => quote!( #mock_var.add_expect_behaviour(#ufc_trait_name, #method_name, mock::ExpectBehaviour::with_times(#expr, #stmt_id, binding.clone(), #stmt_repr)); ),
-                    &ExpectRepeat::AtLeast(ref expr) => quote!( #mock_var.add_expect_behaviour(#ufc_trait_name, #method_name, mock::ExpectBehaviour::with_at_least(#expr, #stmt_id, binding.clone(), #stmt_repr)); ),
-                    &ExpectRepeat::AtMost(ref expr) => quote!( #mock_var.add_expect_behaviour(#ufc_trait_name, #method_name, mock::ExpectBehaviour::with_at_most(#expr, #stmt_id, binding.clone(), #stmt_repr)); ),
-                    &ExpectRepeat::Between(ref expr_lower, ref expr_upper) => quote!( #mock_var.add_expect_behaviour(#ufc_trait_name, #method_name, mock::ExpectBehaviour::with_between(#expr_lower, #expr_upper, #stmt_id, binding.clone(), #stmt_repr)); ),
-                });
+        let (expect_block, _) = implement_expect_block(binding_fields, expect_definitions, absolute_position);
+        (expect_block.to_string(), remainder.to_owned())
+    } else { panic!("Expecting a `expect_interactions!` definition: <MOCK_VAR_NAME as MOCKED_TRAIT>::METHOD(MATCHER, ...) REPEAT; ..."); }
+}
+
+/// Handles an `expect_interactions_scoped!` block which evaluates to a guard verifying and removing the block's expectations once dropped.
+pub fn handle_expect_interactions_scoped(source: &str, absolute_position: usize) -> (String, String) {
+    if let IResult::Done(remainder, (binding_fields, expect_definitions)) = parse_scoped_expect_interactions(source) {
+        let (expect_block, stmt_ids_per_mock) = implement_expect_block(binding_fields, expect_definitions, absolute_position);
+
+        let drop_actions = stmt_ids_per_mock.into_iter().map(|(mock_var, stmt_ids)| {
+            let stmt_ids = quote!(&[#(#stmt_ids),*]);
+            quote! {
+                #mock_var.verify_expect_behaviours(#stmt_ids);
+                #mock_var.remove_expect_behaviours(#stmt_ids);
+            }
+        }).collect::<Vec<_>>();
+        let guard = implement_create_behaviour_guard(&drop_actions);
+
+        let scoped_expect_block = quote! {{
+            #expect_block
+            #guard
+        }};
+        (scoped_expect_block.to_string(), remainder.to_owned())
+    } else { panic!("Expecting a `expect_interactions_scoped!` definition: <MOCK_VAR_NAME as MOCKED_TRAIT>::METHOD(MATCHER, ...) REPEAT; ..."); }
+}
+
+/// Generates the statement adding the expectation of an expect statement to its mock.
+///
+/// The statement refers to the variable `binding` holding the bound values of the statement's block.
+pub fn implement_add_expect_behaviour(stmt: &ExpectStatement) -> quote::Tokens {
+    let mock_var = &stmt.mock_var;
+    let ufc_trait_name = stmt.trait_name();
+    let method_name = stmt.method_name();
+    let behaviour = implement_create_expect_behaviour(stmt);
+    if !stmt.captures {
+        return quote!( #mock_var.add_expect_behaviour(#ufc_trait_name, #method_name, #behaviour); );
+    }
+
+    let capture_fn = syn::Ident::from(format!("capture_statement_{}", stmt.stmt_id));
+    let statement_fn = implement_statement_fn(&stmt.matcher, None);
+    quote! {{
+        let statement_fn = #mock_var.#capture_fn(#statement_fn);
+        #mock_var.add_expect_behaviour(#ufc_trait_name, #method_name, #behaviour.capturing(statement_fn));
+    }}
+}
+
+/// Generates the expression creating the `ExpectBehaviour` of an expect statement.
+pub fn implement_create_expect_behaviour(stmt: &ExpectStatement) -> quote::Tokens {
+    let stmt_id = stmt.stmt_id;
+    let stmt_repr = format!("{}", stmt);
+    match stmt.repeat {
+        ExpectRepeat::Times(ref expr) => quote!( mock::ExpectBehaviour::with_times(#expr, #stmt_id, binding.clone(), #stmt_repr) ),
+        ExpectRepeat::AtLeast(ref expr) => quote!( mock::ExpectBehaviour::with_at_least(#expr, #stmt_id, binding.clone(), #stmt_repr) ),
+        ExpectRepeat::AtMost(ref expr) => quote!( mock::ExpectBehaviour::with_at_most(#expr, #stmt_id, binding.clone(), #stmt_repr) ),
+        ExpectRepeat::Between(ref expr_lower, ref expr_upper) => quote!( mock::ExpectBehaviour::with_between(#expr_lower, #expr_upper, #stmt_id, binding.clone(), #stmt_repr) ),
+    }
+}
+
+/// Generates the statements adding the expectations of an expect block to the mocks.
+///
+/// Returns the generated statements and the ids of the added statements grouped by mock variable.
+fn implement_expect_block(binding_fields: Vec<BindingField>, expect_definitions: Vec<ExpectStatement>, absolute_position: usize)
+                          -> (quote::Tokens, StmtIdsPerMock) {
+    let mut statements = acquire!(EXPECT_STATEMENTS);
+
+    let binding = Binding {
+        block_id: absolute_position,
+        fields: binding_fields
+    };
+
+    let mut add_statements = Vec::new();
+    let mut stmt_ids_per_mock: StmtIdsPerMock = Vec::new();
+    for (idx, mut stmt) in expect_definitions.into_iter().enumerate() {
+        stmt.block_id = absolute_position;
+        stmt.stmt_id = absolute_position + idx;
+        stmt.captures = can_capture(&stmt.ufc_trait, &stmt.method, &stmt.matcher, None);
+        if binding.has_inferred_types() && !stmt.captures {
+            panic!("Values bound without a type can only be used by statements capturing their environment but `{}` does not. \
+                    State the types of the bound values: bind VARIABLE: TYPE = EXPRESSION;", stmt);
+        }
+        let stmt_id = stmt.stmt_id;
+
+        {
+            let mock_var = &stmt.mock_var;
+            add_statements.push(implement_add_expect_behaviour(&stmt));
+
+            match stmt_ids_per_mock.iter_mut().find(|&&mut (ref var, _)| var == mock_var) {
+                Some(&mut (_, ref mut stmt_ids)) => stmt_ids.push(stmt_id),
+                None => stmt_ids_per_mock.push((mock_var.clone(), vec![stmt_id]))
             }
-            statements.entry(stmt.ufc_trait.clone())
-                      .or_insert_with(|| Vec::new())
-                      .push(stmt);
         }
+        statements.entry(stmt.ufc_trait.clone())
+                  .or_default()
+                  .push(stmt);
+    }
 
-        let binding = Binding {
-            block_id: absolute_position,
-            fields: binding_fields
-        };
-        let binding_initialization = implement_initialize_binding(&binding);
-        acquire!(BINDINGS).push(binding);
+    let binding_initialization = implement_initialize_binding(&binding);
+    acquire!(BINDINGS).push(binding);
 
-        let given_block = quote! {
-            let binding = std::rc::Rc::new(#binding_initialization);
-            #(#add_statements)*
-        };
+    let expect_block = quote! {
+        let binding = std::rc::Rc::new(#binding_initialization);
+        #(#add_statements)*
+    };
 
-        return (given_block.to_string(), remainder.to_owned());
-    } else { panic!("Expecting a `expect_interactions!` definition: <MOCK_VAR_NAME as MOCKED_TRAIT>::METHOD(MATCHER, ...) REPEAT; ..."); }
+    (expect_block, stmt_ids_per_mock)
 }
@@ -16,6 +16,11 @@ use syn;
 use quote;
 
 use super::*;
+use super::return_kind::ReturnKind;
+use super::field_matchers::expand_field_matchers;
+use super::pattern_matcher::PatternMatcher;
+use super::matchers::import_builtin_matchers;
+use super::describe::{given_description_template, expect_description_template, implement_describe_matcher};
 use data::GivenStatement;
 
 pub fn implement_given_behaviour() -> Vec<quote::Tokens> {
@@ -24,34 +29,63 @@ pub fn implement_given_behaviour() -> Vec<quote::Tokens> {
             stmt_id: usize,
             num_matches: std::cell::Cell<usize>,
             expected_matches: Option<usize>,
-            bound: std::rc::Rc<std::any::Any>,
-            stmt_repr: String
+            bound: std::rc::Rc<dyn std::any::Any>,
+            stmt_repr: String,
+            description: std::cell::RefCell<Option<String>>,
+            statement_fn: Option<std::rc::Rc<dyn std::any::Any>>,
+            priority: i32,
+            faults: Option<FaultInjection>
         }
     };
 
     let behaviour_impl = quote! {
         #[allow(dead_code)]
         impl GivenBehaviour {
-            pub fn with(stmt_id: usize, bound: std::rc::Rc<std::any::Any>, stmt_repr: &str) -> Self {
+            pub fn with(stmt_id: usize, bound: std::rc::Rc<dyn std::any::Any>, stmt_repr: &str) -> Self {
                 Self {
                     stmt_id: stmt_id,
                     num_matches: std::cell::Cell::new(0),
                     expected_matches: None,
                     bound: bound,
-                    stmt_repr: stmt_repr.to_string()
+                    stmt_repr: stmt_repr.to_string(),
+                    description: std::cell::RefCell::new(None),
+                    statement_fn: None,
+                    priority: 0,
+                    faults: None
                 }
             }
 
-            pub fn with_times(times: usize, stmt_id: usize, bound: std::rc::Rc<std::any::Any>, stmt_repr: &str) -> Self {
+            pub fn with_times(times: usize, stmt_id: usize, bound: std::rc::Rc<dyn std::any::Any>, stmt_repr: &str) -> Self {
                 Self {
                     stmt_id: stmt_id,
                     num_matches: std::cell::Cell::new(0),
                     expected_matches: Some(times),
                     bound: bound,
-                    stmt_repr: stmt_repr.to_string()
+                    stmt_repr: stmt_repr.to_string(),
+                    description: std::cell::RefCell::new(None),
+                    statement_fn: None,
+                    priority: 0,
+                    faults: None
                 }
             }
 
+            /// Selects the calls matching the behaviour which return an error, see `then_fail_after` and friends.
+            pub fn with_faults(mut self, faults: FaultInjection) -> Self {
+                self.faults = Some(faults);
+                self
+            }
+
+            /// Checks if the current call matching the behaviour should return an error.
+            pub fn injects_fault(&self) -> bool {
+                self.faults.as_ref().map_or(false, |faults| faults.fails(self.num_matches.get()))
+            }
+
+            /// Behaviours with a higher priority are matched before behaviours with a lower one, the default is `0`.
+            pub fn with_priority(mut self, priority: i32) -> Self {
+                self.priority = priority;
+                self
+            }
+
             pub fn matched(&self) {
                 self.num_matches.set(self.num_matches.get() + 1);
             }
@@ -63,64 +97,480 @@ pub fn implement_given_behaviour() -> Vec<quote::Tokens> {
                 }
             }
 
-            pub fn describe(&self) -> &str {
-                &self.stmt_repr
+            /// Describes the statement by its matchers once it has been evaluated, by its source otherwise.
+            pub fn describe(&self) -> String {
+                self.description.borrow().clone().unwrap_or_else(|| self.stmt_repr.clone())
+            }
+
+            /// Describes the statement by the matchers of its first evaluation.
+            ///
+            /// `describe` receives the descriptions of the matchers used for the evaluation, see `describe_statement`.
+            pub fn describe_with<F: FnOnce() -> String>(&self, describe: F) {
+                if self.description.borrow().is_none() {
+                    *self.description.borrow_mut() = Some(describe());
+                }
+            }
+
+            /// Evaluates the statement by the boxed closure `statement_fn`.
+            pub fn capturing(mut self, statement_fn: std::rc::Rc<dyn std::any::Any>) -> Self {
+                self.statement_fn = Some(statement_fn);
+                self
+            }
+
+            pub fn statement_fn(&self) -> Option<&dyn std::any::Any> {
+                self.statement_fn.as_ref().map(|statement_fn| &**statement_fn)
             }
         }
     };
 
-    vec![behaviour_item, behaviour_impl]
+    let explain_item = quote! {
+        /// Explains why the argument matchers of a statement are not satisfied.
+        ///
+        /// `matches` holds the result per matcher, `is_explicit` is set if a single matcher receives all arguments.
+        pub fn explain_unmatched_args(matches: &[Result<(), Option<String>>], is_explicit: bool) -> String {
+            if is_explicit {
+                return match matches.first() {
+                    Some(&Err(Some(ref explanation))) => format!("argument matcher not satisfied: {}", explanation),
+                    _ => String::from("argument matcher not satisfied")
+                };
+            }
+
+            let unmatched_args = matches.iter().enumerate().filter_map(|(idx, arg_match)| match arg_match {
+                &Ok(()) => None,
+                &Err(Some(ref explanation)) => Some(format!("#{} ({})", idx, explanation)),
+                &Err(None) => Some(format!("#{}", idx))
+            }).collect::<Vec<_>>();
+            format!("unmatched arguments: {}", unmatched_args.join(", "))
+        }
+    };
+
+    let apply_item = quote! {
+        /// Calls the function of a `then_return_from` statement evaluated by a closure.
+        ///
+        /// Unlike calling the function directly, the types of the function's arguments are known when checking its body.
+        pub fn apply_return_fn<A, R, F: Fn(&A) -> R>(curried_args: &A, return_fn: F) -> R {
+            return_fn(curried_args)
+        }
+    };
+
+    let faults_item = quote! {
+        /// Selects the calls of a given behaviour which fail. Calls are numbered starting with `1`.
+        pub enum FaultInjection {
+            After(usize),
+            OnCalls(Vec<usize>),
+            Randomly { fraction: f64, seed: u64 }
+        }
+
+        #[allow(dead_code)]
+        impl FaultInjection {
+            pub fn after(num_calls: usize) -> Self {
+                FaultInjection::After(num_calls)
+            }
+
+            pub fn on_calls<I: IntoIterator<Item = usize>>(calls: I) -> Self {
+                FaultInjection::OnCalls(calls.into_iter().collect())
+            }
+
+            /// Each call fails with probability `fraction`. The same `seed` always selects the same calls.
+            pub fn randomly(fraction: f64, seed: u64) -> Self {
+                FaultInjection::Randomly { fraction: fraction, seed: seed }
+            }
+
+            pub fn fails(&self, call: usize) -> bool {
+                match self {
+                    &FaultInjection::After(num_calls) => call > num_calls,
+                    &FaultInjection::OnCalls(ref calls) => calls.contains(&call),
+                    &FaultInjection::Randomly { fraction, seed } => {
+                        // SplitMix64 of the call number
+                        let mut z = seed.wrapping_add((call as u64).wrapping_mul(0x9E3779B97F4A7C15));
+                        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+                        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+                        z ^= z >> 31;
+                        ((z >> 11) as f64 / (1u64 << 53) as f64) < fraction
+                    }
+                }
+            }
+        }
+    };
+
+    vec![behaviour_item, behaviour_impl, explain_item, apply_item, faults_item]
+}
+
+fn implement_return_expr(return_stmt: &Return, return_kind: &ReturnKind) -> quote::Tokens {
+    match *return_stmt {
+        Return::FromValue(ref expr) => return_kind.convert(quote!{ #expr }),
+        Return::FromCall(ref expr) => return_kind.convert(quote!{ (#expr)(&curried_args) }),
+        Return::FromIter(ref expr) => return_kind.convert_iter(quote!{ #expr }),
+        Return::FromSpy => panic!("galvanic_mock internal error: `then_spy_on_object` has no return expression"),
+        Return::Panic | Return::PanicWith(..) | Return::PanicWithPayload(..) => implement_panic(return_stmt),
+        Return::AfterTrigger(ref inner, ref trigger) => match return_kind {
+            &ReturnKind::Future(..) => {
+                let inner_expr = implement_return_expr(inner, &ReturnKind::Value);
+                quote!{ MockFuture::after_trigger(#inner_expr, (#trigger).clone()) }
+            },
+            _ => panic!("`after_trigger` can only be used for methods returning a future.")
+        },
+        Return::FailWith(ref error) => return_kind.convert(quote!{ Err(std::convert::From::from(#error)) }),
+        Return::InjectFaults(ref inner, _, ref error) => {
+            let inner_expr = implement_return_expr(inner, return_kind);
+            let error_expr = return_kind.convert(quote!{ Err(std::convert::From::from(#error)) });
+            quote!{ if behaviour.injects_fault() { #error_expr } else { #inner_expr } }
+        },
+        Return::Delayed(ref inner, ref delay) => {
+            let inner_expr = match *inner {
+                Some(ref inner) => implement_return_expr(inner, return_kind),
+                None => return_kind.convert(quote!{ () })
+            };
+            let delay = implement_delay(delay);
+            quote!{ { #delay; #inner_expr } }
+        }
+    }
+}
+
+fn implement_panic(return_stmt: &Return) -> quote::Tokens {
+    match *return_stmt {
+        Return::PanicWith(ref message) => quote!{ panic!("{}", #message) },
+        Return::PanicWithPayload(ref payload) => quote!{ std::panic::panic_any(#payload) },
+        _ => quote!{ panic!("Panic by behaviour. Don't forget the towel.") }
+    }
+}
+
+fn implement_delay(delay: &Delay) -> quote::Tokens {
+    match *delay {
+        Delay::Sleep(ref duration) => quote!{ std::thread::sleep(#duration) },
+        Delay::BlockUntil(ref barrier) => quote!{ (#barrier).wait() }
+    }
+}
+
+/// The argument matchers of a statement for a single evaluation of the statement.
+///
+/// Each matcher expression is evaluated once into its instance `matcher_IDX` which is used to match the argument,
+/// to explain a mismatch, and to describe the statement. `pat!` matchers are matched by generated code instead
+/// which evaluates once into `bindings_IDX`, i.e., the pattern's bindings if the argument matches.
+struct ArgMatchers<'a> {
+    /// The matcher expressions and the place expressions of the matched values.
+    matchers: Vec<(&'a syn::Expr, String)>
+}
+
+impl<'a> ArgMatchers<'a> {
+    /// Creates the matchers of a statement for the arguments tuple `args`.
+    fn of(matcher: &'a BehaviourMatcher, args: &str) -> Self {
+        let matchers = match *matcher {
+            BehaviourMatcher::Void => Vec::new(),
+            BehaviourMatcher::Explicit(ref expr) => vec![(expr, args.to_string())],
+            BehaviourMatcher::PerArgument(ref exprs) => exprs.iter().enumerate()
+                                                              .map(|(idx, expr)| (expr, format!("{}.{}", args, idx)))
+                                                              .collect()
+        };
+        ArgMatchers { matchers }
+    }
+
+    fn instance(idx: usize) -> syn::Ident {
+        syn::Ident::from(format!("matcher_{}", idx))
+    }
+
+    fn bindings(idx: usize) -> syn::Ident {
+        syn::Ident::from(format!("bindings_{}", idx))
+    }
+
+    /// Generates the statements creating the matchers' instances and declaring the bindings of the `pat!` matchers.
+    fn implement_create(&self) -> quote::Tokens {
+        let mut tokens = quote::Tokens::new();
+        for (idx, &(expr, ref value)) in self.matchers.iter().enumerate() {
+            match PatternMatcher::detect(expr) {
+                Some(_) => {
+                    let bindings = ArgMatchers::bindings(idx);
+                    tokens.append(quote!{ let mut #bindings = None; });
+                },
+                None => {
+                    let instance = ArgMatchers::instance(idx);
+                    let matcher = expand_field_matchers(expr, &format!("&{}", value));
+                    tokens.append(quote!{ let #instance = #matcher; });
+                }
+            }
+        }
+        tokens
+    }
+
+    /// Generates the results of matching the arguments, i.e., `Ok(())` or `Err` with an optional explanation.
+    ///
+    /// The matchers are evaluated in order until the first one is not satisfied,
+    /// so the results end with the first `Err` and the remaining matchers are not evaluated.
+    fn implement_arg_matches(&self) -> quote::Tokens {
+        if self.matchers.is_empty() {
+            return quote!{ Vec::new() };
+        }
+
+        let arg_matches = self.matchers.iter().enumerate().map(|(idx, &(expr, ref value))| match PatternMatcher::detect(expr) {
+            Some(pattern) => {
+                let bindings = ArgMatchers::bindings(idx);
+                let is_match = pattern.implement_match(value);
+                quote!{ {
+                    #bindings = #is_match;
+                    if #bindings.is_some() { Ok(()) } else { Err(None) }
+                } }
+            },
+            None => {
+                let mut tokens = quote::Tokens::new();
+                tokens.append(format!("if {matcher}.match_args(&{value}) {{ Ok(()) }} else {{ Err({matcher}.explain_mismatch(&{value})) }}",
+                                      matcher = ArgMatchers::instance(idx), value = value));
+                tokens
+            }
+        }).collect::<Vec<_>>();
+
+        quote!{ {
+            let mut arg_matches: Vec<Result<(), Option<String>>> = Vec::new();
+            #(
+                if arg_matches.last().map_or(true, |arg_match| arg_match.is_ok()) {
+                    arg_matches.push(#arg_matches);
+                }
+            )*
+            arg_matches
+        } }
+    }
+
+    /// Generates the descriptions of the matchers' instances.
+    fn implement_descriptions(&self) -> Vec<quote::Tokens> {
+        self.matchers.iter().enumerate()
+            .map(|(idx, &(expr, ref value))| implement_describe_matcher(expr, &ArgMatchers::instance(idx), value))
+            .collect()
+    }
+
+    /// Makes the bindings of the matched `pat!` matchers available to `expr`.
+    ///
+    /// `unmatched` is evaluated instead if a pattern did not match, i.e., the arguments did not match.
+    fn bind_patterns(&self, expr: quote::Tokens, unmatched: quote::Tokens) -> quote::Tokens {
+        let patterns = self.matchers.iter().enumerate()
+                           .filter_map(|(idx, &(expr, _))| PatternMatcher::detect(expr).map(|pattern| (idx, pattern)))
+                           .collect::<Vec<_>>();
+        if patterns.is_empty() {
+            return expr;
+        }
+
+        let bindings = patterns.iter().map(|&(idx, _)| ArgMatchers::bindings(idx)).collect::<Vec<_>>();
+        let bound = patterns.iter().map(|(_, pattern)| pattern.implement_bindings()).collect::<Vec<_>>();
+        quote!{
+            if let (#(Some(#bound),)*) = (#(#bindings,)*) { #expr } else { #unmatched }
+        }
+    }
 }
 
-pub fn implement_given_behaviour_matcher(statement: &GivenStatement) -> quote::Tokens {
-    let return_expr = match &statement.return_stmt {
-        &Return::FromValue(ref expr) => quote!{ #expr },
-        &Return::FromCall(ref expr) => quote!{ (#expr)(&curried_args) },
-        &Return::FromSpy => panic!("return_from_spy is not implemented yet."),
-        &Return::Panic => quote!{ panic!("Panic by behaviour. Don't forget the towel.") }
+/// Creates the value of a statement's return expression evaluated by a closure, see `CapturingMethod`.
+fn implement_raw_return_expr(return_stmt: &Return) -> quote::Tokens {
+    match *return_stmt {
+        Return::FromValue(ref expr) | Return::FromIter(ref expr) => quote!{ #expr },
+        Return::FromCall(ref expr) => quote!{ apply_return_fn(curried_args, #expr) },
+        Return::FromSpy => panic!("galvanic_mock internal error: `then_spy_on_object` is not evaluated by a closure"),
+        Return::FailWith(ref error) => quote!{ Err(std::convert::From::from(#error)) },
+        Return::InjectFaults(ref inner, _, ref error) => {
+            let inner_expr = implement_raw_return_expr(inner);
+            quote!{ if injects_fault() { Err(std::convert::From::from(#error)) } else { #inner_expr } }
+        },
+        Return::Delayed(ref inner, ref delay) => {
+            let inner_expr = match *inner {
+                Some(ref inner) => implement_raw_return_expr(inner),
+                None => quote!{ () }
+            };
+            let delay = implement_delay(delay);
+            quote!{ { #delay; #inner_expr } }
+        },
+        Return::Panic | Return::PanicWith(..) | Return::PanicWithPayload(..) => implement_panic(return_stmt),
+        Return::AfterTrigger(ref inner, ref trigger) => {
+            let inner_expr = implement_raw_return_expr(inner);
+            quote!{ (#inner_expr, (#trigger).clone()) }
+        }
+    }
+}
+
+/// Generates the closure evaluating a statement which captures the environment of the statement's block.
+///
+/// The closure is passed to the mock's `capture_statement_ID` method which determines its argument types.
+/// The bound values of the block are available as `bound`, see `CapturingMethod` for the closure's signature.
+/// Expect statements have no `return_stmt`.
+pub fn implement_statement_fn(matcher: &BehaviourMatcher, return_stmt: Option<&Return>) -> quote::Tokens {
+    let arg_matchers = ArgMatchers::of(matcher, "(*curried_args)");
+    let create_matchers = arg_matchers.implement_create();
+    let arg_matches = arg_matchers.implement_arg_matches();
+    let descriptions = arg_matchers.implement_descriptions();
+
+    let evaluate = match return_stmt {
+        None => quote!{
+            decide(&arg_matches, &describe_matchers);
+            None
+        },
+        Some(return_stmt @ &Return::Panic) | Some(return_stmt @ &Return::PanicWith(..)) | Some(return_stmt @ &Return::PanicWithPayload(..)) => {
+            let panic = implement_panic(return_stmt);
+            quote!{
+                if decide(&arg_matches, &describe_matchers) { #panic } else { None }
+            }
+        },
+        Some(return_stmt) => {
+            let return_expr = implement_raw_return_expr(return_stmt);
+            let evaluate = arg_matchers.bind_patterns(quote!{ Some(#return_expr) }, quote!{ None });
+            quote!{
+                if decide(&arg_matches, &describe_matchers) { #evaluate } else { None }
+            }
+        }
     };
 
-    let match_expr = match statement.matcher {
-        BehaviourMatcher::Void => quote!{ true },
-        BehaviourMatcher::Explicit(ref expr) => quote!{ (#expr).match_args(&curried_args) },
-        BehaviourMatcher::PerArgument(ref exprs) => {
-            let mut arg_tokens = quote::Tokens::new();
-            arg_tokens.append("(");
-            for idx in 0..exprs.len() {
-                if idx >= 1 {
-                    arg_tokens.append("&&");
+    let import_matchers = import_builtin_matchers(quote!(self::mock));
+    quote!{ {
+        let bound = binding.clone();
+        move |curried_args, decide, injects_fault| {
+            use self::mock::*;
+            #import_matchers
+            let bound = &*bound;
+            #create_matchers
+            let arg_matches: Vec<Result<(), Option<String>>> = #arg_matches;
+            let describe_matchers = || -> Vec<String> { vec![#(#descriptions),*] };
+            #evaluate
+        }
+    } }
+}
+
+/// Generates the statement describing a statement by the matchers of its current evaluation if it has not been described yet.
+///
+/// The descriptions of the matchers are created by the expression `descriptions` of type `Vec<String>`.
+fn implement_describe_statement(template: Option<String>, descriptions: quote::Tokens) -> quote::Tokens {
+    match template {
+        Some(template) => quote!{ behaviour.describe_with(|| describe_statement(#template, &#descriptions)); },
+        None => quote!{}
+    }
+}
+
+/// Generates the evaluation of a given statement by its boxed closure of type `statement_fn_ty`.
+pub fn implement_captured_given_behaviour_matcher(statement: &GivenStatement, statement_fn_ty: &quote::Tokens) -> quote::Tokens {
+    let stmt_id = statement.stmt_id;
+    let is_explicit = matches!(statement.matcher, BehaviourMatcher::Explicit(..));
+    let describe = implement_describe_statement(given_description_template(statement), quote!(describe_matchers()));
+    quote! {
+        if behaviour.stmt_id == #stmt_id {
+            let statement_fn = behaviour.statement_fn()
+                                        .and_then(|statement_fn| statement_fn.downcast_ref::<#statement_fn_ty>())
+                                        .expect("galvanic_mock internal error: unable to downcast statement closure");
+            let value = statement_fn(&curried_args, &mut |arg_matches: &[Result<(), Option<String>>], describe_matchers: &dyn Fn() -> Vec<String>| {
+                #describe
+                if arg_matches.iter().all(|arg_match| arg_match.is_ok()) {
+                    behaviour.matched();
+                    true
+                } else {
+                    unmatched_given_behaviours.push(format!("{} ({})", behaviour.describe(),
+                                                            explain_unmatched_args(arg_matches, #is_explicit)));
+                    false
                 }
-                let expr = exprs.get(idx).unwrap();
-                arg_tokens.append(quote!( (#expr) ));
-                arg_tokens.append(format!(".match_args(&curried_args.{})", idx));
+            }, &|| behaviour.injects_fault());
+            if value.is_some() {
+                return_value = value;
+                break;
             }
-            arg_tokens.append(")");
-            arg_tokens
+        }
+    }
+}
+
+/// Generates the evaluation of an expect statement by its boxed closure of type `statement_fn_ty`.
+pub fn implement_captured_expect_behaviour_matcher(statement: &ExpectStatement, statement_fn_ty: &quote::Tokens) -> quote::Tokens {
+    let stmt_id = statement.stmt_id;
+    let describe = implement_describe_statement(expect_description_template(statement), quote!(describe_matchers()));
+    quote! {
+        if behaviour.stmt_id == #stmt_id {
+            let statement_fn = behaviour.statement_fn()
+                                        .and_then(|statement_fn| statement_fn.downcast_ref::<#statement_fn_ty>())
+                                        .expect("galvanic_mock internal error: unable to downcast statement closure");
+            statement_fn(&curried_args, &mut |arg_matches: &[Result<(), Option<String>>], describe_matchers: &dyn Fn() -> Vec<String>| {
+                #describe
+                let num_matched_args = arg_matches.iter().filter(|arg_match| arg_match.is_ok()).count();
+                if num_matched_args == arg_matches.len() {
+                    behaviour.matched();
+                    matched_expect_behaviour = Some(behaviour.describe());
+                } else if nearest_expect_behaviour.as_ref().map_or(true, |&(_, num_matched)| num_matched_args > num_matched) {
+                    nearest_expect_behaviour = Some((behaviour.describe(), num_matched_args));
+                }
+                false
+            }, &|| false);
+            if matched_expect_behaviour.is_some() {
+                break;
+            }
+        }
+    }
+}
+
+pub fn implement_given_behaviour_matcher(statement: &GivenStatement, return_kind: &ReturnKind) -> quote::Tokens {
+    let arg_matchers = ArgMatchers::of(&statement.matcher, "curried_args");
+    // the call is forwarded to the spied object after the given behaviours are evaluated so that the arguments can be moved
+    let apply_behaviour = match statement.return_stmt {
+        Return::FromSpy => quote!{ is_spied_call = true; },
+        ref return_stmt => {
+            let return_expr = implement_return_expr(return_stmt, return_kind);
+            arg_matchers.bind_patterns(quote!{ return_value = Some(#return_expr); }, quote!{})
         }
     };
 
+    let is_explicit = matches!(statement.matcher, BehaviourMatcher::Explicit(..));
+    let create_matchers = arg_matchers.implement_create();
+    let arg_matches = arg_matchers.implement_arg_matches();
+    let descriptions = arg_matchers.implement_descriptions();
+    let describe = implement_describe_statement(given_description_template(statement), quote!([#(#descriptions),*]));
+
     let stmt_id = statement.stmt_id;
-    let return_value = syn::Ident::from("return_value");
-    let behaviour_idx = syn::Ident::from("idx");
-    let maybe_remove_idx = syn::Ident::from("maybe_remove_idx");
     let binding_type = binding_name_for(statement.block_id);
     quote! {
         if behaviour.stmt_id == #stmt_id {
             let bound = behaviour.bound.downcast_ref::<#binding_type>()
                                        .expect("galvanic_mock internal error: unable to downcast binding type");
-            use std::convert::Into;
-            if (#match_expr).into() {
+            #create_matchers
+            let arg_matches: Vec<Result<(), Option<String>>> = #arg_matches;
+            #describe
+            if !arg_matches.iter().all(|arg_match| arg_match.is_ok()) {
+                unmatched_given_behaviours.push(format!("{} ({})", behaviour.describe(),
+                                                        explain_unmatched_args(&arg_matches, #is_explicit)));
+            } else {
                 behaviour.matched();
-                #return_value = Some(#return_expr);
-                if behaviour.is_saturated() {
-                    #maybe_remove_idx = Some(#behaviour_idx);
-                }
+                #apply_behaviour
                 break;
             }
         }
     }
 }
 
+/// Generates the check if an exhausted given statement would have matched the call, evaluated by its boxed closure.
+pub fn implement_captured_exhausted_behaviour_matcher(statement: &GivenStatement, statement_fn_ty: &quote::Tokens) -> quote::Tokens {
+    let stmt_id = statement.stmt_id;
+    quote! {
+        if behaviour.stmt_id == #stmt_id {
+            let statement_fn = behaviour.statement_fn()
+                                        .and_then(|statement_fn| statement_fn.downcast_ref::<#statement_fn_ty>())
+                                        .expect("galvanic_mock internal error: unable to downcast statement closure");
+            statement_fn(&curried_args, &mut |arg_matches: &[Result<(), Option<String>>], _: &dyn Fn() -> Vec<String>| {
+                if arg_matches.iter().all(|arg_match| arg_match.is_ok()) {
+                    exhausted_given_behaviours.push(behaviour.describe());
+                }
+                false
+            }, &|| false);
+        }
+    }
+}
+
+/// Generates the check if an exhausted given statement would have matched the call.
+pub fn implement_exhausted_behaviour_matcher(statement: &GivenStatement) -> quote::Tokens {
+    let arg_matchers = ArgMatchers::of(&statement.matcher, "curried_args");
+    let create_matchers = arg_matchers.implement_create();
+    let arg_matches = arg_matchers.implement_arg_matches();
+
+    let stmt_id = statement.stmt_id;
+    let binding_type = binding_name_for(statement.block_id);
+    quote! {
+        if behaviour.stmt_id == #stmt_id {
+            let bound = behaviour.bound.downcast_ref::<#binding_type>()
+                                       .expect("galvanic_mock internal error: unable to downcast binding type");
+            #create_matchers
+            let arg_matches: Vec<Result<(), Option<String>>> = #arg_matches;
+            if arg_matches.iter().all(|arg_match| arg_match.is_ok()) {
+                exhausted_given_behaviours.push(behaviour.describe());
+            }
+        }
+    }
+}
+
 
 pub fn implement_expect_behaviour() -> Vec<quote::Tokens> {
     let behaviour_item = quote! {
@@ -130,15 +580,17 @@ pub fn implement_expect_behaviour() -> Vec<quote::Tokens> {
             expected_min_matches: Option<usize>,
             expected_max_matches: Option<usize>,
             in_order: Option<bool>,
-            bound: std::rc::Rc<std::any::Any>,
-            stmt_repr: String
+            bound: std::rc::Rc<dyn std::any::Any>,
+            stmt_repr: String,
+            description: std::cell::RefCell<Option<String>>,
+            statement_fn: Option<std::rc::Rc<dyn std::any::Any>>
         }
     };
 
     let behaviour_impl = quote! {
         #[allow(dead_code)]
         impl ExpectBehaviour {
-            pub fn with_times(times: usize, stmt_id: usize, bound: std::rc::Rc<std::any::Any>, stmt_repr: &str) -> Self {
+            pub fn with_times(times: usize, stmt_id: usize, bound: std::rc::Rc<dyn std::any::Any>, stmt_repr: &str) -> Self {
                 Self {
                     stmt_id: stmt_id,
                     num_matches: std::cell::Cell::new(0),
@@ -146,11 +598,13 @@ pub fn implement_expect_behaviour() -> Vec<quote::Tokens> {
                     expected_max_matches: Some(times),
                     in_order: None,
                     bound: bound,
-                    stmt_repr: stmt_repr.to_string()
+                    stmt_repr: stmt_repr.to_string(),
+                    description: std::cell::RefCell::new(None),
+                    statement_fn: None
                 }
             }
 
-            pub fn with_at_least(at_least_times: usize, stmt_id: usize, bound: std::rc::Rc<std::any::Any>, stmt_repr: &str) -> Self {
+            pub fn with_at_least(at_least_times: usize, stmt_id: usize, bound: std::rc::Rc<dyn std::any::Any>, stmt_repr: &str) -> Self {
                 Self {
                     stmt_id: stmt_id,
                     num_matches: std::cell::Cell::new(0),
@@ -158,11 +612,13 @@ pub fn implement_expect_behaviour() -> Vec<quote::Tokens> {
                     expected_max_matches: None,
                     in_order: None,
                     bound: bound,
-                    stmt_repr: stmt_repr.to_string()
+                    stmt_repr: stmt_repr.to_string(),
+                    description: std::cell::RefCell::new(None),
+                    statement_fn: None
                 }
             }
 
-            pub fn with_at_most(at_most_times: usize, stmt_id: usize, bound: std::rc::Rc<std::any::Any>, stmt_repr: &str) -> Self {
+            pub fn with_at_most(at_most_times: usize, stmt_id: usize, bound: std::rc::Rc<dyn std::any::Any>, stmt_repr: &str) -> Self {
                 Self {
                     stmt_id: stmt_id,
                     num_matches: std::cell::Cell::new(0),
@@ -170,11 +626,13 @@ pub fn implement_expect_behaviour() -> Vec<quote::Tokens> {
                     expected_max_matches: Some(at_most_times),
                     in_order: None,
                     bound: bound,
-                    stmt_repr: stmt_repr.to_string()
+                    stmt_repr: stmt_repr.to_string(),
+                    description: std::cell::RefCell::new(None),
+                    statement_fn: None
                 }
             }
 
-            pub fn with_between(at_least_times: usize, at_most_times: usize, stmt_id: usize, bound: std::rc::Rc<std::any::Any>, stmt_repr: &str) -> Self {
+            pub fn with_between(at_least_times: usize, at_most_times: usize, stmt_id: usize, bound: std::rc::Rc<dyn std::any::Any>, stmt_repr: &str) -> Self {
                 Self {
                     stmt_id: stmt_id,
                     num_matches: std::cell::Cell::new(0),
@@ -182,7 +640,9 @@ pub fn implement_expect_behaviour() -> Vec<quote::Tokens> {
                     expected_max_matches: Some(at_most_times),
                     in_order: None,
                     bound: bound,
-                    stmt_repr: stmt_repr.to_string()
+                    stmt_repr: stmt_repr.to_string(),
+                    description: std::cell::RefCell::new(None),
+                    statement_fn: None
                 }
             }
 
@@ -195,8 +655,66 @@ pub fn implement_expect_behaviour() -> Vec<quote::Tokens> {
                     && self.num_matches.get() <= self.expected_max_matches.unwrap_or(std::usize::MAX)
             }
 
-            pub fn describe(&self) -> &str {
-                &self.stmt_repr
+            pub fn num_matches(&self) -> usize {
+                self.num_matches.get()
+            }
+
+            pub fn status(&self) -> ExpectationStatus {
+                if self.num_matches.get() < self.expected_min_matches.unwrap_or(0) {
+                    ExpectationStatus::TooFewMatches
+                } else if self.num_matches.get() > self.expected_max_matches.unwrap_or(std::usize::MAX) {
+                    ExpectationStatus::TooManyMatches
+                } else {
+                    ExpectationStatus::Satisfied
+                }
+            }
+
+            pub fn result(&self, mock: &'static str, requested_trait: &'static str, method: &'static str) -> ExpectationResult {
+                ExpectationResult {
+                    mock: mock,
+                    requested_trait: requested_trait,
+                    method: method,
+                    stmt_id: self.stmt_id,
+                    statement: self.describe(),
+                    expected_min_matches: self.expected_min_matches,
+                    expected_max_matches: self.expected_max_matches,
+                    num_matches: self.num_matches.get(),
+                    status: self.status(),
+                }
+            }
+
+            pub fn describe_expected_matches(&self) -> String {
+                match (self.expected_min_matches, self.expected_max_matches) {
+                    (Some(min), Some(max)) if min == max => format!("exactly {}", min),
+                    (Some(min), Some(max)) => format!("between {} and {}", min, max),
+                    (Some(min), None) => format!("at least {}", min),
+                    (None, Some(max)) => format!("at most {}", max),
+                    (None, None) => String::from("any number of")
+                }
+            }
+
+            /// Describes the statement by its matchers once it has been evaluated, by its source otherwise.
+            pub fn describe(&self) -> String {
+                self.description.borrow().clone().unwrap_or_else(|| self.stmt_repr.clone())
+            }
+
+            /// Describes the statement by the matchers of its first evaluation.
+            ///
+            /// `describe` receives the descriptions of the matchers used for the evaluation, see `describe_statement`.
+            pub fn describe_with<F: FnOnce() -> String>(&self, describe: F) {
+                if self.description.borrow().is_none() {
+                    *self.description.borrow_mut() = Some(describe());
+                }
+            }
+
+            /// Evaluates the statement by the boxed closure `statement_fn`.
+            pub fn capturing(mut self, statement_fn: std::rc::Rc<dyn std::any::Any>) -> Self {
+                self.statement_fn = Some(statement_fn);
+                self
+            }
+
+            pub fn statement_fn(&self) -> Option<&dyn std::any::Any> {
+                self.statement_fn.as_ref().map(|statement_fn| &**statement_fn)
             }
         }
     };
@@ -205,24 +723,11 @@ pub fn implement_expect_behaviour() -> Vec<quote::Tokens> {
 }
 
 pub fn implement_expect_behaviour_matcher(statement: &ExpectStatement) -> quote::Tokens {
-    let match_expr = match statement.matcher {
-        BehaviourMatcher::Void => quote!{ true },
-        BehaviourMatcher::Explicit(ref expr) => quote!{ (#expr).match_args(&curried_args) },
-        BehaviourMatcher::PerArgument(ref exprs) => {
-            let mut arg_tokens = quote::Tokens::new();
-            arg_tokens.append("(");
-            for idx in 0..exprs.len() {
-                if idx >= 1 {
-                    arg_tokens.append("&&");
-                }
-                let expr = exprs.get(idx).unwrap();
-                arg_tokens.append(quote!( (#expr) ));
-                arg_tokens.append(format!(".match_args(&curried_args.{})", idx));
-            }
-            arg_tokens.append(")");
-            arg_tokens
-        }
-    };
+    let arg_matchers = ArgMatchers::of(&statement.matcher, "curried_args");
+    let create_matchers = arg_matchers.implement_create();
+    let arg_matches = arg_matchers.implement_arg_matches();
+    let descriptions = arg_matchers.implement_descriptions();
+    let describe = implement_describe_statement(expect_description_template(statement), quote!([#(#descriptions),*]));
 
     let stmt_id = statement.stmt_id;
     let binding_type = binding_name_for(statement.block_id);
@@ -230,11 +735,59 @@ pub fn implement_expect_behaviour_matcher(statement: &ExpectStatement) -> quote:
         if behaviour.stmt_id == #stmt_id {
             let bound = behaviour.bound.downcast_ref::<#binding_type>()
                                        .expect("galvanic_mock internal error: unable to downcast binding type");
-            use std::convert::Into;
-            if (#match_expr).into() {
+            #create_matchers
+            let arg_matches: Vec<Result<(), Option<String>>> = #arg_matches;
+            #describe
+            let num_matched_args = arg_matches.iter().filter(|arg_match| arg_match.is_ok()).count();
+            if num_matched_args == arg_matches.len() {
                 behaviour.matched();
+                matched_expect_behaviour = Some(behaviour.describe());
                 break;
             }
+            if nearest_expect_behaviour.as_ref().map_or(true, |&(_, num_matched)| num_matched_args > num_matched) {
+                nearest_expect_behaviour = Some((behaviour.describe(), num_matched_args));
+            }
         }
     }
 }
+
+/// Generates a guard type executing actions, e.g., removing behaviours from mocks, when it is dropped.
+pub fn implement_behaviour_guard() -> Vec<quote::Tokens> {
+    let guard_item = quote! {
+        pub struct BehaviourGuard<'a> {
+            drop_actions: Vec<Box<dyn Fn() + 'a>>
+        }
+    };
+
+    let guard_impl = quote! {
+        #[allow(dead_code)]
+        impl<'a> BehaviourGuard<'a> {
+            pub fn new() -> Self {
+                Self { drop_actions: Vec::new() }
+            }
+
+            pub fn on_drop<F: Fn() + 'a>(&mut self, action: F) {
+                self.drop_actions.push(Box::new(action));
+            }
+        }
+
+        impl<'a> std::ops::Drop for BehaviourGuard<'a> {
+            fn drop(&mut self) {
+                for action in self.drop_actions.iter() {
+                    action();
+                }
+            }
+        }
+    };
+
+    vec![guard_item, guard_impl]
+}
+
+/// Generates an expression creating a `BehaviourGuard` which executes the `drop_actions` once dropped.
+pub fn implement_create_behaviour_guard(drop_actions: &[quote::Tokens]) -> quote::Tokens {
+    quote! {{
+        let mut guard = mock::BehaviourGuard::new();
+        #( guard.on_drop(|| { #drop_actions }); )*
+        guard
+    }}
+}
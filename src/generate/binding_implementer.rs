@@ -24,17 +24,31 @@ pub fn implement_bindings(bindings: &[Binding]) -> Vec<quote::Tokens> {
     bindings.into_iter().map(|binding| implement_binding(binding)).collect::<Vec<_>>()
 }
 
+/// Generates the struct holding the bound values of a block.
+///
+/// Each field with an inferred type gets its own type parameter, the struct is instantiated by the initialization.
+/// Values bound with `bind mut` are wrapped in a `RefCell` so behaviours can update them.
 fn implement_binding(binding: &Binding) -> quote::Tokens {
     let binding_name = binding_name_for(binding.block_id);
+    let mut ty_params = Vec::new();
     let fields = binding.fields.iter().map(|field| {
         let name = &field.name;
-        let ty = &field.ty;
-        quote!(pub #name: #ty)
+        let ty = match field.ty {
+            Some(ref ty) => quote!(#ty),
+            None => {
+                let ty_param = syn::Ident::from(format!("BoundValue{}", ty_params.len()));
+                let ty = quote!(#ty_param);
+                ty_params.push(ty_param);
+                ty
+            }
+        };
+        if field.is_mut {
+            quote!(pub #name: std::cell::RefCell<#ty>)
+        } else { quote!(pub #name: #ty) }
     }).collect::<Vec<_>>();
 
     quote!{
-        #[derive(Clone)]
-        pub(crate) struct #binding_name {
+        pub(crate) struct #binding_name<#(#ty_params),*> {
             #(#fields),*
         }
     }
@@ -45,7 +59,9 @@ pub fn implement_initialize_binding(binding: &Binding) -> quote::Tokens {
     let field_initializers = binding.fields.iter().map(|field| {
         let name = &field.name;
         let initializer = &field.initializer;
-        quote!(#name: #initializer)
+        if field.is_mut {
+            quote!(#name: std::cell::RefCell::new(#initializer))
+        } else { quote!(#name: #initializer) }
     }).collect::<Vec<_>>();
 
     quote!{
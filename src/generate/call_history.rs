@@ -0,0 +1,77 @@
+/* Copyright 2017 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use quote;
+
+/// Generates the types for recording the calls of mocked methods.
+///
+/// Arguments are recorded in their `Debug` representation if available, otherwise as `_`.
+/// The selection uses auto-ref based dispatch: `(&DebugArg(&arg)).debug_repr()` resolves to
+/// `DebugArgRepr` if the argument implements `Debug` and to `FallbackArgRepr` otherwise.
+pub fn implement_call_record() -> Vec<quote::Tokens> {
+    let debug_arg = quote! {
+        pub struct DebugArg<'a, T: 'a>(pub &'a T);
+
+        pub trait DebugArgRepr {
+            fn debug_repr(&self) -> String;
+        }
+
+        impl<'a, T: std::fmt::Debug + 'a> DebugArgRepr for DebugArg<'a, T> {
+            fn debug_repr(&self) -> String {
+                format!("{:?}", self.0)
+            }
+        }
+
+        pub trait FallbackArgRepr {
+            fn debug_repr(&self) -> String;
+        }
+
+        impl<'a, 'b, T: 'a> FallbackArgRepr for &'b DebugArg<'a, T> {
+            fn debug_repr(&self) -> String {
+                String::from("_")
+            }
+        }
+    };
+
+    let record_item = quote! {
+        #[derive(Clone, Debug)]
+        pub struct CallRecord {
+            pub mock: &'static str,
+            pub sequence: usize,
+            pub requested_trait: &'static str,
+            pub method: &'static str,
+            pub args: Vec<String>,
+            pub matched_expect_behaviour: Option<String>,
+            pub nearest_expect_behaviour: Option<(String, usize)>
+        }
+    };
+
+    let record_impl = quote! {
+        #[allow(dead_code)]
+        impl CallRecord {
+            pub fn describe(&self) -> String {
+                let call = format!("{}({})", self.method, self.args.join(", "));
+                match (&self.matched_expect_behaviour, &self.nearest_expect_behaviour) {
+                    (&Some(ref matched), _) => format!("{} matched: {}", call, matched),
+                    (&None, &Some((ref nearest, num_matched_args))) =>
+                        format!("{} unmatched, nearest expectation: {} (argument matcher #{} not satisfied)",
+                                call, nearest, num_matched_args),
+                    (&None, &None) => format!("{} unmatched", call)
+                }
+            }
+        }
+    };
+
+    vec![debug_arg, record_item, record_impl]
+}
@@ -0,0 +1,288 @@
+/* Copyright 2017 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use syn;
+use quote;
+
+use super::{InstantiatedTrait, find_instantiated_trait, is_receiver};
+use super::return_kind::ReturnKind;
+use data::*;
+
+/// Checks if the statements for `method` of `ufc_trait` can be evaluated by closures capturing their environment.
+///
+/// Given statements pass their `return_stmt`, expect statements pass `None`.
+/// Returns `false` if the trait is not known (yet), so the statement falls back to being implemented in the mock.
+///
+/// # Panics
+/// Panics if the trait is not known but the statement refers to variables of the surrounding scope,
+/// as these are not accessible from within the mock.
+pub fn can_capture(ufc_trait: &syn::Path, method: &syn::Ident, matcher: &BehaviourMatcher, return_stmt: Option<&Return>) -> bool {
+    let inst_trait = find_instantiated_trait(ufc_trait, &acquire!(MOCKABLE_TRAITS));
+    let inst_trait = match inst_trait {
+        Some(inst_trait) => inst_trait,
+        None => {
+            let mut exprs = matcher.exprs();
+            exprs.extend(return_stmt.map_or(Vec::new(), |return_stmt| return_stmt.exprs()));
+            let variables = scope_variables(&exprs);
+            if !variables.is_empty() {
+                panic!("The statement for `{}::{}` uses the variables `{}` of the surrounding scope, but the trait is not known yet. \
+                        Capturing variables requires the trait to be declared with #[mockable] before the #[use_mocks] item using it, \
+                        otherwise the values must be bound: bind VARIABLE: TYPE = EXPRESSION;",
+                       quote!(#ufc_trait), method, variables.iter().map(|var| var.as_ref()).collect::<Vec<_>>().join("`, `"));
+            }
+            return false;
+        }
+    };
+
+    match CapturingMethod::for_method(&inst_trait, method) {
+        Some(capturing_method) => match return_stmt {
+            Some(return_stmt) => capturing_method.raw_return(return_stmt).is_some(),
+            None => true
+        },
+        None => false
+    }
+}
+
+/// Collects the variables of the surrounding scope referred to by `exprs`.
+///
+/// This is a heuristic: lowercase identifiers which are neither bound within the expressions (e.g., as closure parameters)
+/// nor called as functions are considered to be variables. Values bound in the `given!` block are accessed via `bound`.
+/// The arguments of macros are not inspected.
+fn scope_variables(exprs: &[&syn::Expr]) -> Vec<syn::Ident> {
+    let mut variables = ScopeVariables { bound: vec![syn::Ident::from("bound")], used: Vec::new() };
+    for expr in exprs {
+        syn::visit::Visitor::visit_expr(&mut variables, expr);
+    }
+
+    let ScopeVariables { bound, mut used } = variables;
+    used.retain(|var| !bound.contains(var));
+    used
+}
+
+fn is_path(expr: &syn::Expr) -> bool {
+    matches!(expr.node, syn::ExprKind::Path(..))
+}
+
+struct ScopeVariables {
+    bound: Vec<syn::Ident>,
+    used: Vec<syn::Ident>
+}
+
+impl syn::visit::Visitor for ScopeVariables {
+    fn visit_pat(&mut self, pat: &syn::Pat) {
+        if let syn::Pat::Ident(_, ref ident, _) = *pat {
+            self.bound.push(ident.clone());
+        }
+        syn::visit::walk_pat(self, pat);
+    }
+
+    fn visit_expr(&mut self, expr: &syn::Expr) {
+        match expr.node {
+            syn::ExprKind::Path(None, ref path) => {
+                let is_variable = !path.global && path.segments.len() == 1
+                                  && path.segments[0].parameters.is_empty()
+                                  && path.segments[0].ident.as_ref().starts_with(|c: char| c.is_lowercase() || c == '_');
+                if is_variable && !self.used.contains(&path.segments[0].ident) {
+                    self.used.push(path.segments[0].ident.clone());
+                }
+            },
+            syn::ExprKind::Call(ref func, ref args) => {
+                // functions are called by their path, e.g., the built-in matchers
+                if !is_path(func) {
+                    self.visit_expr(func);
+                }
+                for arg in args {
+                    self.visit_expr(arg);
+                }
+            },
+            _ => syn::visit::walk_expr(self, expr)
+        }
+    }
+}
+
+/// A mocked method whose statements are evaluated by closures capturing the environment of their block.
+///
+/// The closure of a statement has the type
+/// `Fn(&(ARGS,), &mut dyn FnMut(&[Result<(), Option<String>>], &dyn Fn() -> Vec<String>) -> bool, &dyn Fn() -> bool) -> Option<RAW>`.
+/// It evaluates the argument matchers and passes the result per matcher to the callback,
+/// i.e., `Ok(())` or `Err` with an optional explanation, together with a function describing the evaluated matchers.
+/// If the callback decides that the statement is applied, the closure evaluates the statement's return value.
+/// The last argument checks if the applied statement injects a fault, see `then_fail_after` and friends.
+/// Expect statements never return a value.
+///
+/// The closures are only possible if all argument types and the return type can be named outside of the trait's
+/// implementation, e.g., the method is not generic and the values returned do not borrow from `self`.
+pub struct CapturingMethod {
+    arg_tys: Vec<syn::Ty>,
+    return_kind: ReturnKind,
+    return_ty: syn::Ty
+}
+
+/// The type of the value returned by the closure of a given statement and its conversion to the stored value.
+struct RawReturn {
+    ty: quote::Tokens,
+    /// If not empty `ty` is the type parameter `CapturedReturn` with the stated bounds.
+    bounds: Vec<quote::Tokens>,
+    convert: quote::Tokens
+}
+
+impl RawReturn {
+    fn named(ty: quote::Tokens, convert: quote::Tokens) -> Option<RawReturn> {
+        Some(RawReturn { ty, bounds: Vec::new(), convert })
+    }
+
+    fn generic(bounds: Vec<quote::Tokens>, convert: quote::Tokens) -> Option<RawReturn> {
+        Some(RawReturn { ty: quote!(CapturedReturn), bounds, convert })
+    }
+}
+
+impl CapturingMethod {
+    /// Returns `None` if the statements of `method` cannot be evaluated by closures.
+    pub fn for_method(inst_trait: &InstantiatedTrait, method: &syn::Ident) -> Option<CapturingMethod> {
+        if !inst_trait.info.generics.lifetimes.is_empty() {
+            return None;
+        }
+
+        let signature = inst_trait.info.items.iter().filter_map(|item| match item.node {
+            syn::TraitItemKind::Method(ref signature, _) if item.ident == *method => Some(signature),
+            _ => None
+        }).next()?;
+        let is_plain = signature.generics.ty_params.is_empty() && signature.generics.lifetimes.is_empty()
+                       && signature.abi.is_none() && signature.unsafety == syn::Unsafety::Normal
+                       && signature.decl.inputs.iter().any(is_receiver);
+        if !is_plain {
+            return None;
+        }
+
+        let arg_tys = signature.decl.inputs.iter().filter(|arg| !is_receiver(arg)).filter_map(|arg| match arg {
+            &syn::FnArg::Captured(_, ref ty) | &syn::FnArg::Ignored(ref ty) => Some(inst_trait.mapper.instantiate_from_ty(ty)),
+            _ => None
+        }).collect::<Vec<_>>();
+        let (return_kind, return_ty) = inst_trait.return_of(method, signature);
+
+        let are_args_nameable = arg_tys.iter().all(|ty| !mentions(ty, |token| token == "Self" || token == "impl"));
+        let is_return_nameable = !mentions(&return_ty, |token| token == "Self" || token.starts_with('&') || token.starts_with('\''))
+                                 && !matches!((&return_kind, &return_ty), (ReturnKind::Value, syn::Ty::ImplTrait(..)));
+        if are_args_nameable && is_return_nameable {
+            Some(CapturingMethod { arg_tys, return_kind, return_ty })
+        } else { None }
+    }
+
+    /// The type of the boxed closures as stored by the mock.
+    ///
+    /// Only given statements return a value, combined given-and-expect statements share the closure.
+    pub fn statement_fn_ty(&self, returns_value: bool) -> quote::Tokens {
+        let args_ty = self.args_ty();
+        let value_ty = if returns_value { self.value_ty() } else { quote!(()) };
+        quote!{ std::boxed::Box<dyn Fn(&#args_ty, &mut dyn FnMut(&[Result<(), Option<String>>], &dyn Fn() -> Vec<String>) -> bool, &dyn Fn() -> bool) -> Option<#value_ty>> }
+    }
+
+    /// Generates the mock's method boxing the statement's closure.
+    pub fn implement_capture_method(&self, stmt_id: usize, return_stmt: Option<&Return>) -> quote::Tokens {
+        let capture_fn = syn::Ident::from(format!("capture_statement_{}", stmt_id));
+
+        let raw_return = match return_stmt {
+            Some(return_stmt) => self.raw_return(return_stmt)
+                                     .expect("galvanic_mock internal error: the return value of the statement cannot be captured"),
+            None => RawReturn { ty: quote!(()), bounds: Vec::new(), convert: quote!(value) }
+        };
+        let value_ty = match return_stmt {
+            Some(..) => self.value_ty(),
+            None => quote!(())
+        };
+        let statement_fn_ty = self.statement_fn_ty(return_stmt.is_some());
+        let type_params = if raw_return.bounds.is_empty() { quote!(F) } else { quote!(F, CapturedReturn) };
+        let raw_ty = &raw_return.ty;
+        let bounds = &raw_return.bounds;
+        let convert = &raw_return.convert;
+
+        let args_ty = self.args_ty();
+        quote! {
+            #[doc(hidden)]
+            #[allow(dead_code)]
+            pub fn #capture_fn<#type_params>(&self, statement_fn: F) -> std::rc::Rc<dyn std::any::Any>
+            where F: Fn(&#args_ty, &mut dyn FnMut(&[Result<(), Option<String>>], &dyn Fn() -> Vec<String>) -> bool, &dyn Fn() -> bool) -> Option<#raw_ty> + 'static,
+                  #(#bounds),* {
+                let statement_fn: #statement_fn_ty = std::boxed::Box::new(
+                    move |curried_args: &#args_ty, decide: &mut dyn FnMut(&[Result<(), Option<String>>], &dyn Fn() -> Vec<String>) -> bool, injects_fault: &dyn Fn() -> bool| {
+                        statement_fn(curried_args, decide, injects_fault).map(|value| -> #value_ty { #convert })
+                    }
+                );
+                std::rc::Rc::new(statement_fn)
+            }
+        }
+    }
+
+    fn args_ty(&self) -> quote::Tokens {
+        let arg_tys = &self.arg_tys;
+        quote!{ (#(#arg_tys,)*) }
+    }
+
+    /// The type of the value stored by the generated method before it is returned.
+    fn value_ty(&self) -> quote::Tokens {
+        match self.return_kind {
+            ReturnKind::Value => {
+                let return_ty = &self.return_ty;
+                quote!{ #return_ty }
+            },
+            ref return_kind => return_kind.value_ty()
+        }
+    }
+
+    /// Determines the type of the value returned by the closure of a given statement, or `None` if not supported.
+    fn raw_return(&self, return_stmt: &Return) -> Option<RawReturn> {
+        let value = quote!(value);
+        match *return_stmt {
+            Return::FromSpy => None,
+            // the injected error is returned like `then_fail_with` instead of the inner value
+            Return::InjectFaults(ref inner, ..) => match **inner {
+                Return::FromValue(..) | Return::FromCall(..) | Return::FailWith(..) => self.raw_return(inner),
+                _ => None
+            },
+            Return::Panic | Return::PanicWith(..) | Return::PanicWithPayload(..) => RawReturn::named(self.value_ty(), value),
+            Return::FromValue(..) | Return::FromCall(..) | Return::FailWith(..) => match self.return_kind {
+                ReturnKind::Value => RawReturn::named(self.value_ty(), value),
+                ReturnKind::Future(_, ref output) => RawReturn::named(quote!(#output), self.return_kind.convert(value)),
+                ReturnKind::ImplIterator(ref item) => RawReturn::generic(
+                    vec![quote!(CapturedReturn: Iterator<Item = #item> + 'static)],
+                    self.return_kind.convert(value)
+                ),
+                ReturnKind::BoxedIterator(ref ty) => RawReturn::named(quote!(#ty), value),
+            },
+            Return::FromIter(..) => self.return_kind.iterator_item().and_then(|item| RawReturn::generic(
+                vec![quote!(CapturedReturn: IntoIterator<Item = #item>),
+                     quote!(<CapturedReturn as IntoIterator>::IntoIter: 'static)],
+                self.return_kind.convert_iter(value)
+            )),
+            Return::Delayed(Some(ref inner), _) => self.raw_return(inner),
+            Return::Delayed(None, _) => match self.return_kind {
+                ReturnKind::Value => RawReturn::named(self.value_ty(), value),
+                _ => None
+            },
+            Return::AfterTrigger(ref inner, _) => match (&**inner, &self.return_kind) {
+                (&Return::FromValue(..), &ReturnKind::Future(_, ref output))
+                | (&Return::FailWith(..), &ReturnKind::Future(_, ref output))
+                | (&Return::FromCall(..), &ReturnKind::Future(_, ref output)) => RawReturn::named(
+                    quote!((#output, FutureTrigger)),
+                    quote!(MockFuture::after_trigger(value.0, value.1))
+                ),
+                _ => None
+            }
+        }
+    }
+}
+
+fn mentions<P: Fn(&str) -> bool>(ty: &syn::Ty, predicate: P) -> bool {
+    quote!(#ty).to_string().split_whitespace().any(predicate)
+}
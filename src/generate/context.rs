@@ -0,0 +1,224 @@
+/* Copyright 2017 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use quote;
+
+/// Generates the recorder keeping track of a mock's expectations and calls.
+///
+/// The recorder is shared between a mock and the `MockContext` it is registered in,
+/// so the context is able to verify the mock even after the mock itself has been dropped.
+pub fn implement_mock_recorder() -> Vec<quote::Tokens> {
+    let recorder_item = quote! {
+        pub struct MockRecorder {
+            pub mock_name: &'static str,
+            pub expect_behaviours: std::cell::RefCell<std::collections::HashMap<(&'static str, &'static str), Vec<ExpectBehaviour>>>,
+            pub calls: std::cell::RefCell<Vec<CallRecord>>,
+            /// Refers to the `MockContext` the mock is registered with while the context is alive.
+            pub context: std::cell::RefCell<Option<std::rc::Weak<MockContextState>>>,
+            /// Set once a `MockContext` verified the mock.
+            pub is_verified_by_context: std::cell::Cell<bool>,
+        }
+
+        thread_local! {
+            static CALL_SEQUENCE: std::cell::Cell<usize> = std::cell::Cell::new(0);
+        }
+    };
+
+    let recorder_impl = quote! {
+        #[allow(dead_code)]
+        impl MockRecorder {
+            pub fn new(mock_name: &'static str) -> Self {
+                Self {
+                    mock_name: mock_name,
+                    expect_behaviours: std::cell::RefCell::new(std::collections::HashMap::new()),
+                    calls: std::cell::RefCell::new(Vec::new()),
+                    context: std::cell::RefCell::new(None),
+                    is_verified_by_context: std::cell::Cell::new(false),
+                }
+            }
+
+            /// Records a call, the sequence number of the call is unique among all mocks of the thread.
+            pub fn record_call(&self, requested_trait: &'static str, method: &'static str, args: Vec<String>,
+                               matched_expect_behaviour: Option<String>, nearest_expect_behaviour: Option<(String, usize)>) {
+                let sequence = CALL_SEQUENCE.with(|sequence| {
+                    let next = sequence.get();
+                    sequence.set(next + 1);
+                    next
+                });
+                self.calls.borrow_mut().push(CallRecord {
+                    mock: self.mock_name,
+                    sequence: sequence,
+                    requested_trait: requested_trait,
+                    method: method,
+                    args: args,
+                    matched_expect_behaviour: matched_expect_behaviour,
+                    nearest_expect_behaviour: nearest_expect_behaviour
+                });
+            }
+
+            pub fn verification_report(&self) -> VerificationReport {
+                let mut expectations = self.expect_behaviours.borrow().iter()
+                    .flat_map(|(&(requested_trait, method), behaviours)| {
+                        behaviours.iter().map(move |behaviour| behaviour.result(self.mock_name, requested_trait, method))
+                    }).collect::<Vec<_>>();
+                expectations.sort_by_key(|expectation| expectation.stmt_id);
+                VerificationReport { expectations: expectations }
+            }
+        }
+    };
+
+    let recorder_describe_impl = quote! {
+        #[allow(dead_code)]
+        impl MockRecorder {
+            pub fn describe_unsatisfied_expect_behaviours(&self, stmt_ids: Option<&[usize]>) -> String {
+                let all_behaviours = self.expect_behaviours.borrow();
+                let mut unsatisfied = all_behaviours.iter()
+                    .flat_map(|(key, behaviours)| behaviours.iter().map(move |behaviour| (key, behaviour)))
+                    .filter(|&(_, behaviour)| stmt_ids.map_or(true, |ids| ids.contains(&behaviour.stmt_id)) && !behaviour.is_saturated())
+                    .collect::<Vec<_>>();
+                unsatisfied.sort_by_key(|&(_, behaviour)| behaviour.stmt_id);
+
+                let calls = self.calls.borrow();
+                let mut report = String::new();
+                for (&(requested_trait, method), behaviour) in unsatisfied {
+                    report.push_str(&format!("\n\tBehaviour unsatisfied: {}\n\t\texpected {} matching calls, but got {}",
+                                             behaviour.describe(), behaviour.describe_expected_matches(), behaviour.num_matches()));
+
+                    let method_calls = calls.iter()
+                                            .filter(|call| call.requested_trait == requested_trait && call.method == method)
+                                            .collect::<Vec<_>>();
+                    if method_calls.is_empty() {
+                        report.push_str(&format!("\n\t\tno calls of <{}>::{}", requested_trait, method));
+                    } else {
+                        report.push_str(&format!("\n\t\tcalls of <{}>::{}:", requested_trait, method));
+                        for call in method_calls {
+                            report.push_str(&format!("\n\t\t\t{}", call.describe()));
+                        }
+                    }
+                }
+                report
+            }
+        }
+    };
+
+    vec![recorder_item, recorder_impl, recorder_describe_impl]
+}
+
+/// Generates the `MockContext` which verifies all mocks created during its lifetime together.
+///
+/// Mocks register with the innermost active context of the current thread when they are created.
+/// Mocks dropped while their context is alive are not verified on drop, instead the context verifies them once it is dropped.
+/// If the context does not verify on drop the mocks verify themselves on drop as if they were not registered.
+/// Mocks outliving their context are verified on drop unless the context verified them.
+pub fn implement_mock_context() -> Vec<quote::Tokens> {
+    let context_item = quote! {
+        /// The state of a `MockContext` shared with the recorders of its mocks.
+        pub struct MockContextState {
+            recorders: std::cell::RefCell<Vec<std::rc::Rc<MockRecorder>>>,
+            verify_on_drop: std::cell::Cell<bool>,
+        }
+
+        pub struct MockContext {
+            state: std::rc::Rc<MockContextState>,
+        }
+
+        /// The states of the active contexts of a thread, the innermost context is the last one.
+        type ActiveContexts = std::cell::RefCell<Vec<std::rc::Rc<MockContextState>>>;
+
+        thread_local! {
+            static ACTIVE_CONTEXTS: ActiveContexts = std::cell::RefCell::new(Vec::new());
+        }
+    };
+
+    let context_impl = quote! {
+        #[allow(dead_code)]
+        impl MockContext {
+            pub fn new() -> Self {
+                let state = std::rc::Rc::new(MockContextState {
+                    recorders: std::cell::RefCell::new(Vec::new()),
+                    verify_on_drop: std::cell::Cell::new(true),
+                });
+                ACTIVE_CONTEXTS.with(|contexts| contexts.borrow_mut().push(state.clone()));
+                Self { state: state }
+            }
+
+            /// Registers the recorder of a new mock with the innermost active context.
+            /// Returns `false` if there is no active context.
+            pub fn register(recorder: &std::rc::Rc<MockRecorder>) -> bool {
+                ACTIVE_CONTEXTS.with(|contexts| match contexts.borrow().last() {
+                    Some(state) => {
+                        *recorder.context.borrow_mut() = Some(std::rc::Rc::downgrade(state));
+                        state.recorders.borrow_mut().push(recorder.clone());
+                        true
+                    },
+                    None => false
+                })
+            }
+
+            /// If called with `false` the registered mocks verify themselves on drop instead.
+            pub fn should_verify_on_drop(&mut self, flag: bool) { self.state.verify_on_drop.set(flag); }
+
+            /// Returns `true` if the recorder is registered with a live context which verifies it on drop.
+            pub fn verifies_on_drop(recorder: &MockRecorder) -> bool {
+                recorder.context.borrow().as_ref()
+                        .and_then(|context| context.upgrade())
+                        .map_or(false, |state| state.verify_on_drop.get())
+            }
+
+            /// Returns the state of the expectations of all registered mocks in the order of their registration.
+            pub fn verification_report(&self) -> VerificationReport {
+                VerificationReport {
+                    expectations: self.state.recorders.borrow().iter()
+                                      .flat_map(|recorder| recorder.verification_report().expectations)
+                                      .collect()
+                }
+            }
+
+            /// Returns the calls of all registered mocks in the order they occured.
+            pub fn call_history(&self) -> Vec<CallRecord> {
+                let mut calls = self.state.recorders.borrow().iter()
+                                    .flat_map(|recorder| recorder.calls.borrow().clone())
+                                    .collect::<Vec<_>>();
+                calls.sort_by_key(|call| call.sequence);
+                calls
+            }
+
+            pub fn verify(&self) {
+                let report = self.state.recorders.borrow().iter().map(|recorder| {
+                    recorder.is_verified_by_context.set(true);
+                    let mock_report = recorder.describe_unsatisfied_expect_behaviours(None);
+                    if mock_report.is_empty() { mock_report } else { format!("\n{}:{}", recorder.mock_name, mock_report) }
+                }).collect::<String>();
+                if !report.is_empty() && !std::thread::panicking() {
+                    panic!("There are unsatisfied expected behaviours for mocked traits:{}", report);
+                }
+            }
+        }
+
+        impl std::ops::Drop for MockContext {
+            fn drop(&mut self) {
+                let state = &self.state;
+                ACTIVE_CONTEXTS.with(|contexts| contexts.borrow_mut().retain(|context| !std::rc::Rc::ptr_eq(context, state)));
+                if self.state.verify_on_drop.get() {
+                    self.verify();
+                }
+                for recorder in self.state.recorders.borrow().iter() {
+                    *recorder.context.borrow_mut() = None;
+                }
+            }
+        }
+    };
+
+    vec![context_item, context_impl]
+}
@@ -0,0 +1,144 @@
+/* Copyright 2017 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use syn;
+use quote;
+
+use super::pattern_matcher::PatternMatcher;
+use data::*;
+
+/// Placeholder for matcher descriptions in the statement's format string.
+const MATCHER_PLACEHOLDER: char = '\u{0}';
+
+/// Generates the types for describing the matchers of given/expect statements.
+///
+/// The selection uses auto-ref based dispatch: `(&&MatcherDescription::new(&matcher, arg_ty)).describe_matcher()`
+/// resolves to `DisplayMatcherDescription` if the matcher implements `Display`, to `ArgMatcherDescription`
+/// which uses `ArgMatcher::describe` otherwise, and to `NoMatcherDescription` if the matcher is no `ArgMatcher`.
+///
+/// `describe_statement` fills in the descriptions of the matchers used by an evaluation of a statement into the
+/// statement's template, see `given_description_template`.
+pub fn implement_matcher_description() -> Vec<quote::Tokens> {
+    let description = quote! {
+        pub struct MatcherDescription<'m, 'a, M: 'm, T: 'a>(&'m M, std::marker::PhantomData<&'a T>);
+
+        #[allow(dead_code)]
+        impl<'m, 'a, M: 'm, T: 'a> MatcherDescription<'m, 'a, M, T> {
+            pub fn new(matcher: &'m M, _: std::marker::PhantomData<T>) -> Self {
+                MatcherDescription(matcher, std::marker::PhantomData)
+            }
+        }
+
+        pub trait DisplayMatcherDescription {
+            fn describe_matcher(&self) -> Option<String>;
+        }
+
+        impl<'m, 'a, 'n, M: std::fmt::Display + 'm, T: 'a> DisplayMatcherDescription for &'n MatcherDescription<'m, 'a, M, T> {
+            fn describe_matcher(&self) -> Option<String> {
+                Some(self.0.to_string())
+            }
+        }
+
+        pub trait ArgMatcherDescription {
+            fn describe_matcher(&self) -> Option<String>;
+        }
+
+        impl<'m, 'a, 'n, 'o, M: ArgMatcher<'a, T> + 'm, T: 'a> ArgMatcherDescription for &'o &'n MatcherDescription<'m, 'a, M, T> {
+            fn describe_matcher(&self) -> Option<String> {
+                self.0.describe()
+            }
+        }
+
+        pub trait NoMatcherDescription {
+            fn describe_matcher(&self) -> Option<String>;
+        }
+
+        impl<'m, 'a, M: 'm, T: 'a> NoMatcherDescription for MatcherDescription<'m, 'a, M, T> {
+            fn describe_matcher(&self) -> Option<String> {
+                None
+            }
+        }
+    };
+
+    let placeholder = MATCHER_PLACEHOLDER;
+    let helpers = quote! {
+        /// Returns the witness of the type of `value`.
+        #[allow(dead_code)]
+        pub fn phantom_of<T>(_: &T) -> std::marker::PhantomData<T> {
+            std::marker::PhantomData
+        }
+
+        #[allow(dead_code)]
+        pub fn describe_statement(template: &str, descriptions: &[String]) -> String {
+            let mut description = String::new();
+            for (idx, part) in template.split(#placeholder).enumerate() {
+                if idx > 0 {
+                    description.push_str(&descriptions[idx - 1]);
+                }
+                description.push_str(part);
+            }
+            description
+        }
+    };
+
+    vec![description, helpers]
+}
+
+/// Creates the template of the given statement's description, see `describe_statement`.
+///
+/// Returns `None` if the statement has no matcher which could be described, so it keeps its source representation.
+pub fn given_description_template(statement: &GivenStatement) -> Option<String> {
+    if is_describable(&statement.matcher) {
+        Some(statement.repr_with(|_| MATCHER_PLACEHOLDER.to_string()))
+    } else { None }
+}
+
+/// Creates the template of the expect statement's description, see `given_description_template`.
+pub fn expect_description_template(statement: &ExpectStatement) -> Option<String> {
+    if is_describable(&statement.matcher) {
+        Some(statement.repr_with(|_| MATCHER_PLACEHOLDER.to_string()))
+    } else { None }
+}
+
+fn is_describable(matcher: &BehaviourMatcher) -> bool {
+    match *matcher {
+        BehaviourMatcher::Void => false,
+        BehaviourMatcher::Explicit(ref expr) => !is_closure(expr),
+        BehaviourMatcher::PerArgument(ref exprs) => !exprs.iter().all(is_closure)
+    }
+}
+
+/// Creates the expression describing the argument matcher `expr` for the argument `value`.
+///
+/// The matcher is described by its instance `matcher`, i.e., the one used to match `value`, falling back to its source.
+pub fn implement_describe_matcher(expr: &syn::Expr, matcher: &syn::Ident, value: &str) -> quote::Tokens {
+    if let Some(pattern) = PatternMatcher::detect(expr) {
+        let description = pattern.describe();
+        return quote!{ String::from(#description) };
+    }
+
+    let source = quote!(#expr).to_string();
+    if is_closure(expr) {
+        return quote!{ String::from(#source) };
+    }
+
+    let mut tokens = quote::Tokens::new();
+    tokens.append(format!("(&&MatcherDescription::new(&{}, phantom_of(&{}))).describe_matcher()", matcher, value));
+    tokens.append(quote!( .unwrap_or_else(|| String::from(#source)) ));
+    tokens
+}
+
+fn is_closure(expr: &syn::Expr) -> bool {
+    matches!(expr.node, syn::ExprKind::Closure(..))
+}
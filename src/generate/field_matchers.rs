@@ -0,0 +1,268 @@
+/* Copyright 2017 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use syn;
+use quote;
+
+use super::matchers::MAX_COMBINED_MATCHERS;
+
+/// Generates the matchers for fields of structs created by `has_field!` and `matches_struct!`.
+///
+/// The field accessors are closures which require the type of the matched value to be known when they are created.
+/// Therefore the matchers are created from a witness of the argument's type, i.e., a reference to the argument or a
+/// `PhantomData` of its type.
+pub fn implement_field_matchers() -> Vec<quote::Tokens> {
+    vec![quote! {
+        /// Values from which the type of a matched argument can be inferred.
+        pub trait ArgWitness<T> {}
+
+        impl<'b, T> ArgWitness<T> for &'b T {}
+
+        impl<T> ArgWitness<T> for std::marker::PhantomData<T> {}
+
+        /// Returns the representation function for values of the witnessed type.
+        pub fn debug_repr_of<V>(_: std::marker::PhantomData<V>, repr: fn(&V) -> String) -> fn(&V) -> String {
+            repr
+        }
+    }, quote! {
+        pub struct FieldMatcher<T, V, F, M> {
+            name: &'static str,
+            access: F,
+            matcher: M,
+            description: String,
+            debug_repr: fn(&V) -> String,
+            arg: std::marker::PhantomData<fn(&T)>
+        }
+
+        impl<T, V, F: Fn(&T) -> &V, M> FieldMatcher<T, V, F, M> {
+            /// Creates a matcher for the field `name` of the argument witnessed by `_witness`, see `has_field!`.
+            pub fn of<W, G>(_witness: W, name: &'static str, access: F, create_matcher: G) -> Self
+            where W: ArgWitness<T>, G: FnOnce(std::marker::PhantomData<V>) -> (M, String, fn(&V) -> String) {
+                let (matcher, description, debug_repr) = create_matcher(std::marker::PhantomData);
+                FieldMatcher {
+                    name: name,
+                    access: access,
+                    matcher: matcher,
+                    description: description,
+                    debug_repr: debug_repr,
+                    arg: std::marker::PhantomData
+                }
+            }
+        }
+
+        impl<'a, T: 'a, V: 'a, F: Fn(&T) -> &V, M: ArgMatcher<'a, V>> ArgMatcher<'a, T> for FieldMatcher<T, V, F, M> {
+            fn match_args(&self, actual: &'a T) -> bool {
+                self.matcher.match_args((self.access)(actual))
+            }
+
+            fn describe(&self) -> Option<String> {
+                Some(format!("with field `{}` {}", self.name, self.description))
+            }
+
+            fn explain_mismatch(&self, actual: &'a T) -> Option<String> {
+                let field = (self.access)(actual);
+                if self.matcher.match_args(field) {
+                    return None;
+                }
+                Some(match self.matcher.explain_mismatch(field) {
+                    Some(explanation) => format!("field `{}`: {}", self.name, explanation),
+                    None => format!("field `{}` was {}, expected {}", self.name, (self.debug_repr)(field), self.description)
+                })
+            }
+        }
+    }, quote! {
+        pub struct StructMatcher<T, M> {
+            name: &'static str,
+            fields: M,
+            arg: std::marker::PhantomData<fn(&T)>
+        }
+
+        impl<T, M> StructMatcher<T, M> {
+            /// Creates a matcher for values of type `T` matching all field matchers in the tuple `fields`, see `matches_struct!`.
+            pub fn of(_: std::marker::PhantomData<T>, name: &'static str, fields: M) -> Self {
+                StructMatcher { name: name, fields: fields, arg: std::marker::PhantomData }
+            }
+
+            fn describe_fields<'a>(&self) -> Option<String> where T: 'a, M: MatcherTuple<'a, T> {
+                self.fields.describe_matchers().map(|descriptions| format!("{} {}", self.name, descriptions.join(", ")))
+            }
+
+            fn explain_fields<'a>(&self, actual: &'a T) -> Option<String> where M: MatcherTuple<'a, T> {
+                let explanations = self.fields.explain_mismatches(actual);
+                if explanations.is_empty() { None } else { Some(explanations.join(", ")) }
+            }
+        }
+
+        impl<'a, T: 'a, M: MatcherTuple<'a, T>> ArgMatcher<'a, T> for StructMatcher<T, M> {
+            fn match_args(&self, actual: &'a T) -> bool { self.fields.match_all(actual) }
+
+            fn describe(&self) -> Option<String> { self.describe_fields() }
+
+            fn explain_mismatch(&self, actual: &'a T) -> Option<String> { self.explain_fields(actual) }
+        }
+
+        impl<'a, 'b: 'a, T: 'a, M: MatcherTuple<'a, T>> ArgMatcher<'a, &'b T> for StructMatcher<T, M> {
+            fn match_args(&self, actual: &'a &'b T) -> bool { self.fields.match_all(&**actual) }
+
+            fn describe(&self) -> Option<String> { self.describe_fields() }
+
+            fn explain_mismatch(&self, actual: &'a &'b T) -> Option<String> { self.explain_fields(&**actual) }
+        }
+    }]
+}
+
+/// Expands the `has_field!` and `matches_struct!` invocations of an argument matcher.
+///
+/// `witness` is an expression determining the type of the matched argument, see `implement_field_matchers`.
+/// `has_field!` can be used as the argument matcher itself, as the matcher of a field,
+/// or within `not`, `all_of`, and `any_of`. `matches_struct!` names its type and can be used anywhere.
+pub fn expand_field_matchers(expr: &syn::Expr, witness: &str) -> quote::Tokens {
+    let mut witness_tokens = quote::Tokens::new();
+    witness_tokens.append(witness);
+    expand_matcher(expr, &witness_tokens)
+}
+
+fn expand_matcher(expr: &syn::Expr, witness: &quote::Tokens) -> quote::Tokens {
+    match expr.node {
+        syn::ExprKind::Mac(ref mac) if is_macro(mac, "has_field") => expand_has_field(macro_args(mac), witness),
+        syn::ExprKind::Mac(ref mac) if is_macro(mac, "matches_struct") => expand_matches_struct(macro_args(mac)),
+        syn::ExprKind::Call(ref func, ref args) if is_function(func, "not") && args.len() == 1 => {
+            let matcher = expand_matcher(&args[0], witness);
+            quote!(not(#matcher))
+        },
+        syn::ExprKind::Call(ref func, ref args) if (is_function(func, "all_of") || is_function(func, "any_of")) && args.len() == 1 => {
+            match args[0].node {
+                syn::ExprKind::Tup(ref tuple) => {
+                    let matchers = tuple.iter().map(|matcher| expand_matcher(matcher, witness)).collect::<Vec<_>>();
+                    quote!(#func((#(#matchers),*,)))
+                },
+                _ => expand_nested(expr)
+            }
+        },
+        _ => expand_nested(expr)
+    }
+}
+
+fn is_macro(mac: &syn::Mac, name: &str) -> bool {
+    !mac.path.global && mac.path.segments.len() == 1 && mac.path.segments[0].ident == name
+}
+
+/// Returns the tokens within the parentheses of a macro invocation.
+fn macro_args(mac: &syn::Mac) -> &[syn::TokenTree] {
+    match &mac.tts[..] {
+        &[syn::TokenTree::Delimited(syn::Delimited { delim: syn::DelimToken::Paren, ref tts })] => tts,
+        _ => panic!("Expected parentheses around the arguments of {}!", mac.path.segments[0].ident)
+    }
+}
+
+fn is_function(func: &syn::Expr, name: &str) -> bool {
+    match func.node {
+        syn::ExprKind::Path(None, ref path) => !path.global && path.segments.len() == 1 && path.segments[0].ident == name,
+        _ => false
+    }
+}
+
+/// Expands all `matches_struct!` invocations nested in other expressions.
+fn expand_nested(expr: &syn::Expr) -> quote::Tokens {
+    let expanded = syn::fold::Folder::fold_expr(&mut NestedFieldMatchers, expr.clone());
+    quote!(#expanded)
+}
+
+struct NestedFieldMatchers;
+
+impl syn::fold::Folder for NestedFieldMatchers {
+    fn fold_expr(&mut self, expr: syn::Expr) -> syn::Expr {
+        match expr.node {
+            syn::ExprKind::Mac(ref mac) if is_macro(mac, "has_field") =>
+                panic!("{}", "has_field! can only be used as an argument matcher, as matcher of a field, or within not/all_of/any_of. \
+                              Use matches_struct! within other matchers."),
+            syn::ExprKind::Mac(ref mac) if is_macro(mac, "matches_struct") => {
+                let expanded = expand_matches_struct(macro_args(mac));
+                return syn::parse_expr(expanded.as_str()).expect("galvanic_mock internal error: unable to parse expanded matches_struct!");
+            },
+            _ => {}
+        }
+        syn::fold::noop_fold_expr(self, expr)
+    }
+}
+
+fn split_at_commas(tts: &[syn::TokenTree]) -> Vec<&[syn::TokenTree]> {
+    tts.split(|tt| tt == &syn::TokenTree::Token(syn::Token::Comma))
+       .filter(|part| !part.is_empty())
+       .collect()
+}
+
+fn tokens_to_string(tts: &[syn::TokenTree]) -> String {
+    quote!(#(#tts)*).to_string()
+}
+
+fn parse_matcher(tts: &[syn::TokenTree]) -> syn::Expr {
+    let source = tokens_to_string(tts);
+    syn::parse_expr(&source).unwrap_or_else(|err| panic!("Unable to parse field matcher `{}`: {}", source, err))
+}
+
+/// Expands `has_field!(field, matcher)`.
+fn expand_has_field(args: &[syn::TokenTree], witness: &quote::Tokens) -> quote::Tokens {
+    let comma = args.iter().position(|tt| tt == &syn::TokenTree::Token(syn::Token::Comma))
+                    .expect("has_field! expects a field name and a matcher: has_field!(field, matcher)");
+    expand_field(witness, &args[..comma], &args[comma + 1..])
+}
+
+/// Expands `matches_struct!(Type { field: matcher, .. })`.
+fn expand_matches_struct(args: &[syn::TokenTree]) -> quote::Tokens {
+    let (fields, ty) = match args.split_last() {
+        Some((&syn::TokenTree::Delimited(syn::Delimited { delim: syn::DelimToken::Brace, ref tts }), ty)) if !ty.is_empty() => (tts, ty),
+        _ => panic!("{}", "matches_struct! expects a struct type followed by field matchers: matches_struct!(Type { field: matcher, .. })")
+    };
+    let ty_name = tokens_to_string(ty);
+    let witness = quote!(std::marker::PhantomData::<#(#ty)*>);
+
+    let field_matchers = split_at_commas(fields).into_iter()
+        .filter(|field| field != &[syn::TokenTree::Token(syn::Token::DotDot)])
+        .map(|field| {
+            let colon = field.iter().position(|tt| tt == &syn::TokenTree::Token(syn::Token::Colon))
+                             .expect("The fields of matches_struct! must be given as `field: matcher`");
+            expand_field(&witness, &field[..colon], &field[colon + 1..])
+        }).collect::<Vec<_>>();
+
+    if field_matchers.is_empty() || field_matchers.len() > MAX_COMBINED_MATCHERS {
+        panic!("matches_struct! requires between 1 and {} field matchers: {}", MAX_COMBINED_MATCHERS, ty_name);
+    }
+
+    quote!(matchers::StructMatcher::of(#witness, #ty_name, (#(#field_matchers),*,)))
+}
+
+/// Expands the matcher for the field `name` of the argument witnessed by `witness`.
+///
+/// The field's matcher is created from a witness of the field's type so it may contain field matchers itself.
+/// Its description is determined when it is created as only then its type is known.
+fn expand_field(witness: &quote::Tokens, name: &[syn::TokenTree], matcher: &[syn::TokenTree]) -> quote::Tokens {
+    let name = match *name {
+        [syn::TokenTree::Token(syn::Token::Ident(ref ident))] => ident.to_string(),
+        [syn::TokenTree::Token(syn::Token::Literal(syn::Lit::Int(idx, _)))] => idx.to_string(),
+        _ => panic!("Expected a field name or index instead of: {}", tokens_to_string(name))
+    };
+    let field = syn::Ident::from(name.as_str());
+    let source = tokens_to_string(matcher);
+    let matcher = expand_matcher(&parse_matcher(matcher), &quote!(field));
+
+    quote! {
+        matchers::FieldMatcher::of(#witness, #name, |value| &value.#field, |field| {
+            let matcher = #matcher;
+            let description = (&&MatcherDescription::new(&matcher, field)).describe_matcher()
+                                                                        .unwrap_or_else(|| String::from(#source));
+            (matcher, description, matchers::debug_repr_of(field, |value| (&DebugArg(value)).debug_repr()))
+        })
+    }
+}
@@ -0,0 +1,173 @@
+/* Copyright 2017 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use syn;
+use quote;
+
+/// The shape of a mocked method's return type if the method returns a future.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FutureReturn {
+    /// `impl Future<Output = T>` or an `async fn`
+    Impl,
+    /// `Box<dyn Future<Output = T>>`
+    Boxed,
+    /// `Pin<Box<dyn Future<Output = T>>>`
+    Pinned,
+}
+
+impl FutureReturn {
+    /// Detects if a return type is a future and extracts the type of the future's `Output`.
+    pub fn detect(ty: &syn::Ty) -> Option<(FutureReturn, syn::Ty)> {
+        match *ty {
+            syn::Ty::ImplTrait(ref bounds) => output_of_bounds(bounds).map(|output| (FutureReturn::Impl, output)),
+            syn::Ty::Path(None, ref path) => {
+                let segment = path.segments.last().expect("A type path without segment is not valid.");
+                match (segment.ident.as_ref(), single_type_parameter(segment)) {
+                    ("Box", Some(boxed_ty)) => output_of_trait_object(boxed_ty).map(|output| (FutureReturn::Boxed, output)),
+                    ("Pin", Some(pinned_ty)) => match FutureReturn::detect(pinned_ty) {
+                        Some((FutureReturn::Boxed, output)) => Some((FutureReturn::Pinned, output)),
+                        _ => None
+                    },
+                    _ => None
+                }
+            },
+            _ => None
+        }
+    }
+
+    /// Converts the `MockFuture` stored in the variable `value` to the method's return type.
+    pub fn wrap(&self, value: &syn::Ident) -> quote::Tokens {
+        match *self {
+            FutureReturn::Impl => quote!{ #value },
+            FutureReturn::Boxed => quote!{ std::boxed::Box::new(#value) },
+            FutureReturn::Pinned => quote!{ std::boxed::Box::pin(#value) },
+        }
+    }
+}
+
+fn single_type_parameter(segment: &syn::PathSegment) -> Option<&syn::Ty> {
+    match segment.parameters {
+        syn::PathParameters::AngleBracketed(ref data) if data.types.len() == 1 => data.types.first(),
+        _ => None
+    }
+}
+
+fn output_of_trait_object(ty: &syn::Ty) -> Option<syn::Ty> {
+    match *ty {
+        syn::Ty::Path(None, ref path) => output_of_future_path(path),
+        syn::Ty::TraitObject(ref bounds) => output_of_bounds(bounds),
+        _ => None
+    }
+}
+
+fn output_of_bounds(bounds: &[syn::TyParamBound]) -> Option<syn::Ty> {
+    bounds.iter().filter_map(|bound| match *bound {
+        syn::TyParamBound::Trait(ref poly_trait_ref, _) => output_of_future_path(&poly_trait_ref.trait_ref),
+        _ => None
+    }).next()
+}
+
+fn output_of_future_path(path: &syn::Path) -> Option<syn::Ty> {
+    let segment = path.segments.last().expect("A type path without segment is not valid.");
+    if segment.ident != "Future" {
+        return None;
+    }
+
+    match segment.parameters {
+        syn::PathParameters::AngleBracketed(ref data) => data.bindings.iter()
+                                                             .find(|binding| binding.ident == "Output")
+                                                             .map(|binding| binding.ty.clone()),
+        _ => None
+    }
+}
+
+/// Generates the future type returned by mocked methods returning futures.
+///
+/// A `MockFuture` is either ready immediately or stays pending until its `FutureTrigger` is fired.
+/// Both are `Send` so they can be returned as `Pin<Box<dyn Future<Output = T> + Send>>`.
+pub fn implement_mock_future() -> Vec<quote::Tokens> {
+    let trigger_item = quote! {
+        #[derive(Clone)]
+        pub struct FutureTrigger {
+            state: std::sync::Arc<std::sync::Mutex<(bool, Vec<std::task::Waker>)>>
+        }
+    };
+
+    let trigger_impl = quote! {
+        #[allow(dead_code)]
+        impl FutureTrigger {
+            pub fn new() -> Self {
+                Self { state: std::sync::Arc::new(std::sync::Mutex::new((false, Vec::new()))) }
+            }
+
+            pub fn fire(&self) {
+                let wakers = {
+                    let mut state = self.state.lock().unwrap();
+                    state.0 = true;
+                    std::mem::replace(&mut state.1, Vec::new())
+                };
+                for waker in wakers {
+                    waker.wake();
+                }
+            }
+
+            pub fn is_fired(&self) -> bool {
+                self.state.lock().unwrap().0
+            }
+
+            fn register(&self, waker: &std::task::Waker) {
+                self.state.lock().unwrap().1.push(waker.clone());
+            }
+        }
+    };
+
+    let future_item = quote! {
+        pub struct MockFuture<T> {
+            value: Option<T>,
+            trigger: Option<FutureTrigger>
+        }
+    };
+
+    let future_impl = quote! {
+        #[allow(dead_code)]
+        impl<T> MockFuture<T> {
+            pub fn ready(value: T) -> Self {
+                Self { value: Some(value), trigger: None }
+            }
+
+            pub fn after_trigger(value: T, trigger: FutureTrigger) -> Self {
+                Self { value: Some(value), trigger: Some(trigger) }
+            }
+        }
+
+        impl<T> std::marker::Unpin for MockFuture<T> {}
+
+        impl<T> std::future::Future for MockFuture<T> {
+            type Output = T;
+
+            fn poll(self: std::pin::Pin<&mut Self>, context: &mut std::task::Context) -> std::task::Poll<T> {
+                let this = self.get_mut();
+                if let Some(ref trigger) = this.trigger {
+                    if !trigger.is_fired() {
+                        trigger.register(context.waker());
+                        return std::task::Poll::Pending;
+                    }
+                }
+                std::task::Poll::Ready(this.value.take().expect("MockFuture polled after completion"))
+            }
+        }
+    };
+
+    vec![trigger_item, trigger_impl, future_item, future_impl]
+}
@@ -0,0 +1,438 @@
+/* Copyright 2017 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use syn;
+use quote;
+
+use super::field_matchers::implement_field_matchers;
+
+/// The names of the built-in matcher functions which are made available to argument patterns.
+const BUILTIN_MATCHERS: &[&str] = &["any", "eq", "ne", "lt", "gt", "in_range", "contains", "starts_with",
+                                     "some", "ok", "err", "all_of", "any_of", "not"];
+
+/// The maximal number of matchers which can be combined with `all_of`/`any_of`.
+pub const MAX_COMBINED_MATCHERS: usize = 8;
+
+/// Generates the `matchers` module containing the built-in argument matchers.
+///
+/// The matchers implement `ArgMatcher` and `Display`, the latter describes the matcher in failure messages.
+/// Matchers combining other matchers also describe themselves via `ArgMatcher::describe` if the combined ones do.
+/// Their constructor functions are imported into the `mock` module so they can be used in argument patterns,
+/// unless `galvanic_assert_integration` is enabled (then they have to be called as `matchers::NAME`).
+pub fn implement_builtin_matchers() -> Vec<quote::Tokens> {
+    let mut matchers = quote::Tokens::new();
+    matchers.append_all(implement_value_matchers());
+    matchers.append_all(implement_collection_matchers());
+    matchers.append_all(implement_variant_matchers());
+    matchers.append(implement_combining_matchers());
+    matchers.append_all(implement_field_matchers());
+
+    let import_matchers = if cfg!(feature = "galvanic_assert_integration") {
+        quote::Tokens::new()
+    } else {
+        let matcher_fns = BUILTIN_MATCHERS.iter().map(|name| syn::Ident::from(*name)).collect::<Vec<_>>();
+        quote! {
+            #[allow(unused_imports)]
+            use self::matchers::{#(#matcher_fns),*};
+        }
+    };
+    vec![quote! {
+        pub mod matchers {
+            use std;
+            use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+            use super::ArgMatcher;
+
+            #matchers
+        }
+
+        #import_matchers
+    }]
+}
+
+/// Imports the built-in matchers of the `mock` module at `mock_path` into a closure's body.
+///
+/// With `galvanic_assert_integration` the built-in matchers are not imported, so they cannot shadow galvanic-assert's
+/// matchers of the same name. They are still available as `matchers::NAME`.
+pub fn import_builtin_matchers(mock_path: quote::Tokens) -> quote::Tokens {
+    if cfg!(feature = "galvanic_assert_integration") {
+        quote::Tokens::new()
+    } else {
+        quote!{ use #mock_path::matchers::*; }
+    }
+}
+
+fn implement_value_matchers() -> Vec<quote::Tokens> {
+    vec![quote! {
+        pub struct AnyMatcher;
+
+        /// Matches any value.
+        pub fn any() -> AnyMatcher { AnyMatcher }
+
+        impl<'a, T: 'a> ArgMatcher<'a, T> for AnyMatcher {
+            fn match_args(&self, _: &'a T) -> bool { true }
+        }
+
+        impl Display for AnyMatcher {
+            fn fmt(&self, f: &mut Formatter) -> FmtResult { write!(f, "anything") }
+        }
+    }, quote! {
+        pub struct EqMatcher<V>(V);
+
+        /// Matches values equal to `expected`.
+        pub fn eq<V>(expected: V) -> EqMatcher<V> { EqMatcher(expected) }
+
+        impl<'a, T: PartialEq<V> + 'a, V> ArgMatcher<'a, T> for EqMatcher<V> {
+            fn match_args(&self, actual: &'a T) -> bool { *actual == self.0 }
+        }
+
+        impl<V: Debug> Display for EqMatcher<V> {
+            fn fmt(&self, f: &mut Formatter) -> FmtResult { write!(f, "equal to {:?}", self.0) }
+        }
+    }, quote! {
+        pub struct NeMatcher<V>(V);
+
+        /// Matches values not equal to `unexpected`.
+        pub fn ne<V>(unexpected: V) -> NeMatcher<V> { NeMatcher(unexpected) }
+
+        impl<'a, T: PartialEq<V> + 'a, V> ArgMatcher<'a, T> for NeMatcher<V> {
+            fn match_args(&self, actual: &'a T) -> bool { *actual != self.0 }
+        }
+
+        impl<V: Debug> Display for NeMatcher<V> {
+            fn fmt(&self, f: &mut Formatter) -> FmtResult { write!(f, "not equal to {:?}", self.0) }
+        }
+    }, quote! {
+        pub struct LtMatcher<V>(V);
+
+        /// Matches values less than `bound`.
+        pub fn lt<V>(bound: V) -> LtMatcher<V> { LtMatcher(bound) }
+
+        impl<'a, T: PartialOrd<V> + 'a, V> ArgMatcher<'a, T> for LtMatcher<V> {
+            fn match_args(&self, actual: &'a T) -> bool { *actual < self.0 }
+        }
+
+        impl<V: Debug> Display for LtMatcher<V> {
+            fn fmt(&self, f: &mut Formatter) -> FmtResult { write!(f, "less than {:?}", self.0) }
+        }
+    }, quote! {
+        pub struct GtMatcher<V>(V);
+
+        /// Matches values greater than `bound`.
+        pub fn gt<V>(bound: V) -> GtMatcher<V> { GtMatcher(bound) }
+
+        impl<'a, T: PartialOrd<V> + 'a, V> ArgMatcher<'a, T> for GtMatcher<V> {
+            fn match_args(&self, actual: &'a T) -> bool { *actual > self.0 }
+        }
+
+        impl<V: Debug> Display for GtMatcher<V> {
+            fn fmt(&self, f: &mut Formatter) -> FmtResult { write!(f, "greater than {:?}", self.0) }
+        }
+    }, quote! {
+        pub struct InRangeMatcher<R, V>(R, std::marker::PhantomData<V>);
+
+        /// Matches values within `range`, e.g., `1..5` or `1..`.
+        pub fn in_range<R: std::ops::RangeBounds<V>, V>(range: R) -> InRangeMatcher<R, V> {
+            InRangeMatcher(range, std::marker::PhantomData)
+        }
+
+        impl<'a, T, R, V> ArgMatcher<'a, T> for InRangeMatcher<R, V>
+        where T: PartialOrd<V> + 'a, V: PartialOrd<T>, R: std::ops::RangeBounds<V> {
+            fn match_args(&self, actual: &'a T) -> bool { self.0.contains(actual) }
+        }
+
+        impl<R: Debug, V> Display for InRangeMatcher<R, V> {
+            fn fmt(&self, f: &mut Formatter) -> FmtResult { write!(f, "in range {:?}", self.0) }
+        }
+    }]
+}
+
+fn implement_collection_matchers() -> Vec<quote::Tokens> {
+    vec![quote! {
+        /// Collections and strings which can be checked for containing a value.
+        pub trait ContainsValue<V> {
+            fn contains_value(&self, value: &V) -> bool;
+        }
+
+        impl<X: PartialEq<V>, V> ContainsValue<V> for Vec<X> {
+            fn contains_value(&self, value: &V) -> bool { self.iter().any(|x| x == value) }
+        }
+
+        impl<'b, X: PartialEq<V>, V> ContainsValue<V> for &'b [X] {
+            fn contains_value(&self, value: &V) -> bool { self.iter().any(|x| x == value) }
+        }
+
+        impl<X: PartialEq<V>, V> ContainsValue<V> for std::collections::VecDeque<X> {
+            fn contains_value(&self, value: &V) -> bool { self.iter().any(|x| x == value) }
+        }
+
+        impl<X: PartialEq<V> + Eq + std::hash::Hash, V> ContainsValue<V> for std::collections::HashSet<X> {
+            fn contains_value(&self, value: &V) -> bool { self.iter().any(|x| x == value) }
+        }
+
+        impl<X: PartialEq<V> + Ord, V> ContainsValue<V> for std::collections::BTreeSet<X> {
+            fn contains_value(&self, value: &V) -> bool { self.iter().any(|x| x == value) }
+        }
+
+        impl<'c> ContainsValue<&'c str> for String {
+            fn contains_value(&self, value: &&'c str) -> bool { self.contains(*value) }
+        }
+
+        impl ContainsValue<char> for String {
+            fn contains_value(&self, value: &char) -> bool { self.contains(*value) }
+        }
+
+        impl<'b, 'c> ContainsValue<&'c str> for &'b str {
+            fn contains_value(&self, value: &&'c str) -> bool { self.contains(*value) }
+        }
+
+        impl<'b> ContainsValue<char> for &'b str {
+            fn contains_value(&self, value: &char) -> bool { self.contains(*value) }
+        }
+    }, quote! {
+        pub struct ContainsMatcher<V>(V);
+
+        /// Matches collections containing `value` and strings containing the substring/character `value`.
+        pub fn contains<V>(value: V) -> ContainsMatcher<V> { ContainsMatcher(value) }
+
+        impl<'a, T: ContainsValue<V> + 'a, V> ArgMatcher<'a, T> for ContainsMatcher<V> {
+            fn match_args(&self, actual: &'a T) -> bool { actual.contains_value(&self.0) }
+        }
+
+        impl<V: Debug> Display for ContainsMatcher<V> {
+            fn fmt(&self, f: &mut Formatter) -> FmtResult { write!(f, "containing {:?}", self.0) }
+        }
+    }, quote! {
+        /// Sequences and strings which can be checked for starting with a prefix.
+        pub trait StartsWithPrefix<P> {
+            fn starts_with_prefix(&self, prefix: &P) -> bool;
+        }
+
+        impl<P: AsRef<str>> StartsWithPrefix<P> for String {
+            fn starts_with_prefix(&self, prefix: &P) -> bool { self.starts_with(prefix.as_ref()) }
+        }
+
+        impl<'b, P: AsRef<str>> StartsWithPrefix<P> for &'b str {
+            fn starts_with_prefix(&self, prefix: &P) -> bool { self.starts_with(prefix.as_ref()) }
+        }
+
+        impl<X: PartialEq, P: AsRef<[X]>> StartsWithPrefix<P> for Vec<X> {
+            fn starts_with_prefix(&self, prefix: &P) -> bool { self.starts_with(prefix.as_ref()) }
+        }
+
+        impl<'b, X: PartialEq, P: AsRef<[X]>> StartsWithPrefix<P> for &'b [X] {
+            fn starts_with_prefix(&self, prefix: &P) -> bool { self.starts_with(prefix.as_ref()) }
+        }
+    }, quote! {
+        pub struct StartsWithMatcher<P>(P);
+
+        /// Matches strings and sequences starting with `prefix`.
+        pub fn starts_with<P>(prefix: P) -> StartsWithMatcher<P> { StartsWithMatcher(prefix) }
+
+        impl<'a, T: StartsWithPrefix<P> + 'a, P> ArgMatcher<'a, T> for StartsWithMatcher<P> {
+            fn match_args(&self, actual: &'a T) -> bool { actual.starts_with_prefix(&self.0) }
+        }
+
+        impl<P: Debug> Display for StartsWithMatcher<P> {
+            fn fmt(&self, f: &mut Formatter) -> FmtResult { write!(f, "starting with {:?}", self.0) }
+        }
+    }]
+}
+
+fn implement_variant_matchers() -> Vec<quote::Tokens> {
+    vec![quote! {
+        pub struct SomeMatcher<M>(M);
+
+        /// Matches `Some` values whose content is matched by `matcher`.
+        pub fn some<M>(matcher: M) -> SomeMatcher<M> { SomeMatcher(matcher) }
+
+        impl<'a, T: 'a, M: ArgMatcher<'a, T>> ArgMatcher<'a, Option<T>> for SomeMatcher<M> {
+            fn match_args(&self, actual: &'a Option<T>) -> bool {
+                actual.as_ref().map_or(false, |value| self.0.match_args(value))
+            }
+
+            fn describe(&self) -> Option<String> {
+                self.0.describe().map(|description| format!("Some value {}", description))
+            }
+
+            fn explain_mismatch(&self, actual: &'a Option<T>) -> Option<String> {
+                actual.as_ref().and_then(|value| self.0.explain_mismatch(value))
+            }
+        }
+
+        impl<M: Display> Display for SomeMatcher<M> {
+            fn fmt(&self, f: &mut Formatter) -> FmtResult { write!(f, "Some value {}", self.0) }
+        }
+    }, quote! {
+        pub struct OkMatcher<M>(M);
+
+        /// Matches `Ok` values whose content is matched by `matcher`.
+        pub fn ok<M>(matcher: M) -> OkMatcher<M> { OkMatcher(matcher) }
+
+        impl<'a, T: 'a, E: 'a, M: ArgMatcher<'a, T>> ArgMatcher<'a, Result<T, E>> for OkMatcher<M> {
+            fn match_args(&self, actual: &'a Result<T, E>) -> bool {
+                actual.as_ref().ok().map_or(false, |value| self.0.match_args(value))
+            }
+
+            fn describe(&self) -> Option<String> {
+                self.0.describe().map(|description| format!("Ok value {}", description))
+            }
+
+            fn explain_mismatch(&self, actual: &'a Result<T, E>) -> Option<String> {
+                actual.as_ref().ok().and_then(|value| self.0.explain_mismatch(value))
+            }
+        }
+
+        impl<M: Display> Display for OkMatcher<M> {
+            fn fmt(&self, f: &mut Formatter) -> FmtResult { write!(f, "Ok value {}", self.0) }
+        }
+    }, quote! {
+        pub struct ErrMatcher<M>(M);
+
+        /// Matches `Err` values whose content is matched by `matcher`.
+        pub fn err<M>(matcher: M) -> ErrMatcher<M> { ErrMatcher(matcher) }
+
+        impl<'a, T: 'a, E: 'a, M: ArgMatcher<'a, E>> ArgMatcher<'a, Result<T, E>> for ErrMatcher<M> {
+            fn match_args(&self, actual: &'a Result<T, E>) -> bool {
+                actual.as_ref().err().map_or(false, |error| self.0.match_args(error))
+            }
+
+            fn describe(&self) -> Option<String> {
+                self.0.describe().map(|description| format!("Err value {}", description))
+            }
+
+            fn explain_mismatch(&self, actual: &'a Result<T, E>) -> Option<String> {
+                actual.as_ref().err().and_then(|value| self.0.explain_mismatch(value))
+            }
+        }
+
+        impl<M: Display> Display for ErrMatcher<M> {
+            fn fmt(&self, f: &mut Formatter) -> FmtResult { write!(f, "Err value {}", self.0) }
+        }
+    }, quote! {
+        pub struct NotMatcher<M>(M);
+
+        /// Matches values not matched by `matcher`.
+        pub fn not<M>(matcher: M) -> NotMatcher<M> { NotMatcher(matcher) }
+
+        impl<'a, T: 'a, M: ArgMatcher<'a, T>> ArgMatcher<'a, T> for NotMatcher<M> {
+            fn match_args(&self, actual: &'a T) -> bool { !self.0.match_args(actual) }
+
+            fn describe(&self) -> Option<String> {
+                self.0.describe().map(|description| format!("not {}", description))
+            }
+        }
+
+        impl<M: Display> Display for NotMatcher<M> {
+            fn fmt(&self, f: &mut Formatter) -> FmtResult { write!(f, "not {}", self.0) }
+        }
+    }]
+}
+
+/// Generates `all_of`/`any_of` which combine a tuple of matchers, e.g., `all_of((gt(1), lt(5)))`.
+fn implement_combining_matchers() -> quote::Tokens {
+    let mut tokens = quote! {
+        /// A tuple of matchers for the same type.
+        pub trait MatcherTuple<'a, T: 'a> {
+            fn match_all(&self, actual: &'a T) -> bool;
+            fn match_any(&self, actual: &'a T) -> bool;
+            fn describe_matchers(&self) -> Option<Vec<String>>;
+            fn explain_mismatches(&self, actual: &'a T) -> Vec<String>;
+        }
+
+        /// A tuple of describable matchers.
+        pub trait DisplayTuple {
+            fn describe_all(&self) -> Vec<String>;
+        }
+
+        pub struct AllOfMatcher<M>(M);
+
+        /// Matches values matched by all matchers of the tuple `matchers`.
+        pub fn all_of<M>(matchers: M) -> AllOfMatcher<M> { AllOfMatcher(matchers) }
+
+        impl<'a, T: 'a, M: MatcherTuple<'a, T>> ArgMatcher<'a, T> for AllOfMatcher<M> {
+            fn match_args(&self, actual: &'a T) -> bool { self.0.match_all(actual) }
+
+            fn describe(&self) -> Option<String> {
+                self.0.describe_matchers().map(|descriptions| format!("all of [{}]", descriptions.join(", ")))
+            }
+
+            fn explain_mismatch(&self, actual: &'a T) -> Option<String> {
+                let explanations = self.0.explain_mismatches(actual);
+                if explanations.is_empty() { None } else { Some(explanations.join(", ")) }
+            }
+        }
+
+        impl<M: DisplayTuple> Display for AllOfMatcher<M> {
+            fn fmt(&self, f: &mut Formatter) -> FmtResult { write!(f, "all of [{}]", self.0.describe_all().join(", ")) }
+        }
+
+        pub struct AnyOfMatcher<M>(M);
+
+        /// Matches values matched by any matcher of the tuple `matchers`.
+        pub fn any_of<M>(matchers: M) -> AnyOfMatcher<M> { AnyOfMatcher(matchers) }
+
+        impl<'a, T: 'a, M: MatcherTuple<'a, T>> ArgMatcher<'a, T> for AnyOfMatcher<M> {
+            fn match_args(&self, actual: &'a T) -> bool { self.0.match_any(actual) }
+
+            fn describe(&self) -> Option<String> {
+                self.0.describe_matchers().map(|descriptions| format!("any of [{}]", descriptions.join(", ")))
+            }
+        }
+
+        impl<M: DisplayTuple> Display for AnyOfMatcher<M> {
+            fn fmt(&self, f: &mut Formatter) -> FmtResult { write!(f, "any of [{}]", self.0.describe_all().join(", ")) }
+        }
+    };
+
+    for arity in 1..(MAX_COMBINED_MATCHERS + 1) {
+        let params = (0..arity).map(|idx| format!("M{}", idx)).collect::<Vec<_>>();
+        let indices = (0..arity).map(|idx| idx.to_string()).collect::<Vec<_>>();
+        let tuple_ty = format!("({},)", params.join(", "));
+
+        tokens.append(format!(
+            "impl<'a, T: 'a, {bounds}> MatcherTuple<'a, T> for {tuple_ty} {{
+                fn match_all(&self, actual: &'a T) -> bool {{ {all} }}
+                fn match_any(&self, actual: &'a T) -> bool {{ {any} }}
+                fn describe_matchers(&self) -> Option<Vec<String>> {{ Some(vec![{describe}]) }}
+                fn explain_mismatches(&self, actual: &'a T) -> Vec<String> {{
+                    let mut explanations = Vec::new();
+                    {explain}
+                    explanations
+                }}
+            }}",
+            bounds = params.iter().map(|param| format!("{}: ArgMatcher<'a, T>", param)).collect::<Vec<_>>().join(", "),
+            tuple_ty = tuple_ty,
+            all = indices.iter().map(|idx| format!("self.{}.match_args(actual)", idx)).collect::<Vec<_>>().join(" && "),
+            any = indices.iter().map(|idx| format!("self.{}.match_args(actual)", idx)).collect::<Vec<_>>().join(" || "),
+            describe = indices.iter().map(|idx| format!("self.{}.describe()?", idx)).collect::<Vec<_>>().join(", "),
+            explain = indices.iter().map(|idx| format!(
+                "if !self.{idx}.match_args(actual) {{
+                    explanations.push(self.{idx}.explain_mismatch(actual)
+                                          .or_else(|| self.{idx}.describe().map(|description| format!(\"expected {{}}\", description)))
+                                          .unwrap_or_else(|| String::from(\"not matched\")));
+                }}", idx = idx)).collect::<String>()
+        ));
+
+        tokens.append(format!(
+            "impl<{bounds}> DisplayTuple for {tuple_ty} {{
+                fn describe_all(&self) -> Vec<String> {{ vec![{descriptions}] }}
+            }}",
+            bounds = params.iter().map(|param| format!("{}: Display", param)).collect::<Vec<_>>().join(", "),
+            tuple_ty = tuple_ty,
+            descriptions = indices.iter().map(|idx| format!("self.{}.to_string()", idx)).collect::<Vec<_>>().join(", ")
+        ));
+    }
+
+    tokens
+}
@@ -15,46 +15,100 @@
 use syn;
 use quote;
 
+use data::{RequestedMock, Fixture};
+
 /// Generates mock structs and implementations.
 pub struct MockStructImplementer<'a> {
     /// The name of the mock type
     mock_type_name: &'a syn::Ident,
-    /// The attributes which should be applied to the generated mock
-    attributes: &'a [syn::Attribute],
+    /// The mock as requested by `new_mock!`, e.g., the attributes which should be applied to the generated mock
+    requested_mock: &'a RequestedMock,
 }
 
 impl<'a> MockStructImplementer<'a> {
     /// Create a new mock struct.
-    pub fn for_(mock_type_name: &'a syn::Ident, attributes: &'a [syn::Attribute]) -> Self {
-        MockStructImplementer { mock_type_name, attributes }
+    pub fn for_(mock_type_name: &'a syn::Ident, requested_mock: &'a RequestedMock) -> Self {
+        MockStructImplementer { mock_type_name, requested_mock }
     }
 
     /// Generate the struct definition of the mock and the methods for creating/interacting with the mock.
     pub fn implement(&self) -> Vec<quote::Tokens> {
         let mock_type_name = &self.mock_type_name;
-        let attributes = self.attributes;
+        let mock_name = mock_type_name.to_string();
+        let attributes = &self.requested_mock.attributes;
+        let prefers_latest = self.requested_mock.prefers_latest;
+        let (spy_fields, spy_inits, spy_methods) = self.implement_spy();
+        let (fixture_fields, fixture_inits, fixture_methods, save_fixture) = self.implement_fixture();
 
         let mock_struct = quote! {
             #(#attributes)*
             pub(crate) struct #mock_type_name {
                 given_behaviours: std::cell::RefCell<std::collections::HashMap<(&'static str, &'static str), Vec<GivenBehaviour>>>,
-                expect_behaviours: std::cell::RefCell<std::collections::HashMap<(&'static str, &'static str), Vec<ExpectBehaviour>>>,
+                exhausted_given_behaviours: std::cell::RefCell<std::collections::HashMap<(&'static str, &'static str), Vec<GivenBehaviour>>>,
+                recorder: std::rc::Rc<MockRecorder>,
                 verify_on_drop: bool,
+                prefers_latest_given_behaviours: bool,
+                call_hooks: std::cell::RefCell<Vec<std::rc::Rc<dyn Fn(&str, &str, &[String])>>>,
+                #(#spy_fields,)*
+                #(#fixture_fields,)*
             }
         };
 
         let mock_impl = quote! {
             impl #mock_type_name {
                 pub fn new() -> Self {
+                    let recorder = std::rc::Rc::new(MockRecorder::new(#mock_name));
+                    MockContext::register(&recorder);
                     Self {
                         given_behaviours: std::cell::RefCell::new(std::collections::HashMap::new()),
-                        expect_behaviours: std::cell::RefCell::new(std::collections::HashMap::new()),
+                        exhausted_given_behaviours: std::cell::RefCell::new(std::collections::HashMap::new()),
+                        recorder: recorder,
                         verify_on_drop: true,
+                        prefers_latest_given_behaviours: #prefers_latest,
+                        call_hooks: std::cell::RefCell::new(Vec::new()),
+                        #(#spy_inits,)*
+                        #(#fixture_inits,)*
                     }
                 }
 
+                #(#spy_methods)*
+                #(#fixture_methods)*
+
                 pub fn should_verify_on_drop(&mut self, flag: bool) { self.verify_on_drop = flag; }
 
+                /// If set to `true` the most recently added matching given behaviour is selected instead of the first one.
+                #[allow(dead_code)]
+                pub fn prefer_latest_given_behaviours(&mut self, flag: bool) { self.prefers_latest_given_behaviours = flag; }
+
+                /// Orders the given behaviours of a method in which they are matched, i.e., by descending priority
+                /// and then in the order they have been added (or the reverse order if the latest ones are preferred).
+                #[allow(dead_code)]
+                fn order_given_behaviours<'b>(&self, behaviours: &'b [GivenBehaviour]) -> Vec<&'b GivenBehaviour> {
+                    let mut ordered_behaviours = behaviours.iter().collect::<Vec<_>>();
+                    if self.prefers_latest_given_behaviours {
+                        ordered_behaviours.reverse();
+                    }
+                    ordered_behaviours.sort_by(|behaviour, other| other.priority.cmp(&behaviour.priority));
+                    ordered_behaviours
+                }
+
+                /// Registers a callback invoked with the trait name, the method name, and the `Debug` representations
+                /// of the arguments whenever a mocked method is called, before any behaviour is matched.
+                #[allow(dead_code)]
+                pub fn on_call<F>(&self, hook: F)
+                where F: Fn(&str, &str, &[String]) + 'static {
+                    self.call_hooks.borrow_mut().push(std::rc::Rc::new(hook));
+                }
+
+                #[allow(dead_code)]
+                fn run_call_hooks(&self, requested_trait: &str, method: &str, args: &[String]) {
+                    // the hooks are cloned so that they can register further hooks, which run from the next call on
+                    let call_hooks = self.call_hooks.borrow().clone();
+                    for hook in call_hooks.iter() {
+                        hook(requested_trait, method, args);
+                    }
+                }
+
                 #[allow(dead_code)]
                 pub fn add_given_behaviour(&self, requested_trait: &'static str, method: &'static str, behaviour: GivenBehaviour) {
                     self.given_behaviours.borrow_mut()
@@ -63,14 +117,41 @@ impl<'a> MockStructImplementer<'a> {
                         .push(behaviour);
                 }
 
+                /// Moves the exhausted given behaviours of a method to the ones which only explain unmatched calls.
+                #[allow(dead_code)]
+                fn retire_exhausted_given_behaviours(&self, requested_trait: &'static str, method: &'static str) {
+                    let mut given_behaviours = self.given_behaviours.borrow_mut();
+                    let behaviours = match given_behaviours.get_mut(&(requested_trait, method)) {
+                        Some(behaviours) => behaviours,
+                        None => return
+                    };
+                    while let Some(idx) = behaviours.iter().position(|behaviour| behaviour.is_saturated()) {
+                        self.exhausted_given_behaviours.borrow_mut()
+                            .entry((requested_trait, method))
+                            .or_insert_with(Vec::new)
+                            .push(behaviours.remove(idx));
+                    }
+                }
+
                 #[allow(dead_code)]
                 pub fn reset_given_behaviours(&mut self) {
                     self.given_behaviours.borrow_mut().clear();
+                    self.exhausted_given_behaviours.borrow_mut().clear();
+                }
+
+                #[allow(dead_code)]
+                pub fn remove_given_behaviours(&self, stmt_ids: &[usize]) {
+                    for behaviours in self.given_behaviours.borrow_mut().values_mut() {
+                        behaviours.retain(|behaviour| !stmt_ids.contains(&behaviour.stmt_id));
+                    }
+                    for behaviours in self.exhausted_given_behaviours.borrow_mut().values_mut() {
+                        behaviours.retain(|behaviour| !stmt_ids.contains(&behaviour.stmt_id));
+                    }
                 }
 
                 #[allow(dead_code)]
                 pub fn add_expect_behaviour(&self, requested_trait: &'static str, method: &'static str, behaviour: ExpectBehaviour) {
-                    self.expect_behaviours.borrow_mut()
+                    self.recorder.expect_behaviours.borrow_mut()
                         .entry((requested_trait, method))
                         .or_insert_with(|| Vec::new())
                         .push(behaviour);
@@ -78,30 +159,51 @@ impl<'a> MockStructImplementer<'a> {
 
                 #[allow(dead_code)]
                 pub fn reset_expected_behaviours(&mut self) {
-                    self.expect_behaviours.borrow_mut().clear();
+                    self.recorder.expect_behaviours.borrow_mut().clear();
                 }
 
                 #[allow(dead_code)]
-                pub fn are_expected_behaviours_satisfied(&self) -> bool {
-                    let mut unsatisfied_messages: Vec<String> = Vec::new();
-                    for behaviour in self.expect_behaviours.borrow().values().flat_map(|vs| vs) {
-                        if !behaviour.is_saturated() {
-                            unsatisfied_messages.push(format!("Behaviour unsatisfied: {}", behaviour.describe()));
-                        }
+                pub fn remove_expect_behaviours(&self, stmt_ids: &[usize]) {
+                    for behaviours in self.recorder.expect_behaviours.borrow_mut().values_mut() {
+                        behaviours.retain(|behaviour| !stmt_ids.contains(&behaviour.stmt_id));
                     }
+                }
+            }
+        };
+
+        let mock_verification_impl = quote! {
+            impl #mock_type_name {
+                #[allow(dead_code)]
+                pub fn verify_expect_behaviours(&self, stmt_ids: &[usize]) {
+                    let report = self.describe_unsatisfied_expect_behaviours(Some(stmt_ids));
+                    if !report.is_empty() && !std::thread::panicking() {
+                        panic!("There are unsatisfied expected behaviours for mocked traits:{}", report);
+                    }
+                }
+
+                #[allow(dead_code)]
+                pub fn are_expected_behaviours_satisfied(&self) -> bool {
+                    self.verification_report().is_satisfied()
+                }
 
-                    if !unsatisfied_messages.is_empty() {
-                        for message in unsatisfied_messages {
-                            println!("{}", message);
-                        }
-                        false
-                    } else { true }
+                /// Returns the current state of all expectations ordered by their statement ids without panicking.
+                #[allow(dead_code)]
+                pub fn verification_report(&self) -> VerificationReport {
+                    self.recorder.verification_report()
+                }
+
+                /// Describes all unsatisfied expectations (optionally restricted to some statement ids)
+                /// along with the recorded calls of the expectations' methods.
+                #[allow(dead_code)]
+                pub fn describe_unsatisfied_expect_behaviours(&self, stmt_ids: Option<&[usize]>) -> String {
+                    self.recorder.describe_unsatisfied_expect_behaviours(stmt_ids)
                 }
 
                 #[allow(dead_code)]
                 pub fn verify(&self) {
-                    if !self.are_expected_behaviours_satisfied() && !std::thread::panicking() {
-                        panic!("There are unsatisfied expected behaviours for mocked traits.");
+                    let report = self.describe_unsatisfied_expect_behaviours(None);
+                    if !report.is_empty() && !std::thread::panicking() {
+                        panic!("There are unsatisfied expected behaviours for mocked traits:{}", report);
                     }
                 }
             }
@@ -110,13 +212,129 @@ impl<'a> MockStructImplementer<'a> {
         let mock_drop_impl = quote! {
             impl std::ops::Drop for #mock_type_name {
                 fn drop(&mut self) {
-                    if self.verify_on_drop {
+                    #save_fixture
+                    // the mock's context verifies the mock unless the mock outlives the context or the context does not verify on drop
+                    let is_verified_by_context = MockContext::verifies_on_drop(&self.recorder) || self.recorder.is_verified_by_context.get();
+                    if self.verify_on_drop && !is_verified_by_context {
                         self.verify();
                     }
                 }
             }
         };
 
-        vec![mock_struct, mock_impl, mock_drop_impl]
+        vec![mock_struct, mock_impl, mock_verification_impl, mock_drop_impl]
+    }
+
+    /// Generates the fields, their initializers, and the methods for wrapping a real object, see `#[spy_on(Type)]`.
+    fn implement_spy(&self) -> (Vec<quote::Tokens>, Vec<quote::Tokens>, Vec<quote::Tokens>) {
+        let spied_type = match self.requested_mock.spied_type {
+            Some(ref spied_type) => spied_type,
+            None => return (Vec::new(), Vec::new(), Vec::new())
+        };
+
+        let field = quote!(spied_object: std::cell::RefCell<Option<#spied_type>>);
+        let init = quote!(spied_object: std::cell::RefCell::new(None));
+        let method = quote! {
+            /// Forwards calls stated with `then_spy_on_object` and calls without a matching given behaviour to `object`.
+            #[allow(dead_code)]
+            pub fn spy_on(&self, object: #spied_type) {
+                *self.spied_object.borrow_mut() = Some(object);
+            }
+        };
+        (vec![field], vec![init], vec![method])
+    }
+
+    /// Generates the fields, their initializers, the methods, and the code run on drop for recording/replaying calls.
+    ///
+    /// Calls are stored as JSON objects with the fields `trait`, `method`, `args`, and `returns`.
+    fn implement_fixture(&self) -> (Vec<quote::Tokens>, Vec<quote::Tokens>, Vec<quote::Tokens>, quote::Tokens) {
+        let (path, is_recording) = match self.requested_mock.fixture {
+            Some(Fixture::Record(ref path)) => (path, true),
+            Some(Fixture::Replay(ref path)) => (path, false),
+            None => return (Vec::new(), Vec::new(), Vec::new(), quote::Tokens::new())
+        };
+        let fixture_path = quote!(std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join(#path));
+
+        let field = quote!(fixture_calls: std::cell::RefCell<Vec<(::serde_json::Value, bool)>>);
+        let fixture_call = quote! {
+            fn is_fixture_call(call: &::serde_json::Value, requested_trait: &str, method: &str, args: &[::serde_json::Value]) -> bool {
+                call["trait"] == requested_trait && call["method"] == method
+                    && call["args"].as_array().map_or(false, |call_args| &call_args[..] == args)
+            }
+        };
+
+        if is_recording {
+            let path_field = quote!(fixture_path: std::cell::RefCell<std::path::PathBuf>);
+            let path_init = quote!(fixture_path: std::cell::RefCell::new(#fixture_path));
+            let init = quote!(fixture_calls: std::cell::RefCell::new(Vec::new()));
+            let methods = quote! {
+                #[allow(dead_code)]
+                fn record_fixture_call(&self, requested_trait: &str, method: &str, args: Vec<::serde_json::Value>, returns: ::serde_json::Value) {
+                    let mut call = ::serde_json::Map::new();
+                    call.insert(String::from("trait"), ::serde_json::Value::String(requested_trait.to_string()));
+                    call.insert(String::from("method"), ::serde_json::Value::String(method.to_string()));
+                    call.insert(String::from("args"), ::serde_json::Value::Array(args));
+                    call.insert(String::from("returns"), returns);
+                    self.fixture_calls.borrow_mut().push((::serde_json::Value::Object(call), true));
+                }
+
+                /// Changes the fixture file the recorded calls are written to, relative paths are relative to the crate's manifest directory.
+                #[allow(dead_code)]
+                pub fn record_to<P: AsRef<std::path::Path>>(&self, path: P) {
+                    *self.fixture_path.borrow_mut() = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join(path);
+                }
+
+                /// Writes the calls recorded so far to the fixture file. This is done automatically when the mock is dropped.
+                #[allow(dead_code)]
+                pub fn save_recorded_calls(&self) {
+                    let calls = self.fixture_calls.borrow().iter().map(|&(ref call, _)| call.clone()).collect::<Vec<_>>();
+                    let path = self.fixture_path.borrow();
+                    if let Some(directory) = path.parent() {
+                        std::fs::create_dir_all(directory)
+                            .unwrap_or_else(|err| panic!("Unable to create the directory of the fixture `{}`: {}", path.display(), err));
+                    }
+                    let json = ::serde_json::to_string_pretty(&::serde_json::Value::Array(calls))
+                                   .expect("Unable to serialize the recorded calls");
+                    std::fs::write(&*path, json + "\n")
+                        .unwrap_or_else(|err| panic!("Unable to write the fixture `{}`: {}", path.display(), err));
+                }
+            };
+            let save_fixture = quote! {
+                if !std::thread::panicking() {
+                    self.save_recorded_calls();
+                }
+            };
+            (vec![field, path_field], vec![init, path_init], vec![methods], save_fixture)
+        } else {
+            let init = quote!(fixture_calls: std::cell::RefCell::new({
+                let path = #fixture_path;
+                let json = std::fs::read_to_string(&path)
+                               .unwrap_or_else(|err| panic!("Unable to read the fixture `{}`: {}", path.display(), err));
+                match ::serde_json::from_str(&json) {
+                    Ok(::serde_json::Value::Array(calls)) => calls.into_iter().map(|call| (call, false)).collect(),
+                    _ => panic!("The fixture `{}` is not an array of recorded calls", path.display())
+                }
+            }));
+            let methods = quote! {
+                #fixture_call
+
+                /// Returns the recorded return value of the first unreplayed call with the same arguments.
+                /// If all such calls have been replayed the last one is repeated.
+                #[allow(dead_code)]
+                fn replay_fixture_call(&self, requested_trait: &str, method: &str, args: &[::serde_json::Value]) -> Option<::serde_json::Value> {
+                    let mut fixture_calls = self.fixture_calls.borrow_mut();
+                    let mut matching_calls = fixture_calls.iter_mut()
+                                                          .filter(|&&mut (ref call, _)| Self::is_fixture_call(call, requested_trait, method, args))
+                                                          .collect::<Vec<_>>();
+                    let replayed_call = match matching_calls.iter().position(|&&mut (_, is_replayed)| !is_replayed) {
+                        Some(idx) => matching_calls.swap_remove(idx),
+                        None => matching_calls.pop()?
+                    };
+                    replayed_call.1 = true;
+                    Some(replayed_call.0["returns"].clone())
+                }
+            };
+            (vec![field], vec![init], vec![methods], quote::Tokens::new())
+        }
     }
 }
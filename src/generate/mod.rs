@@ -13,16 +13,36 @@
  * limitations under the License.
  */
 pub mod binding_implementer;
-mod type_param_mapper;
+pub mod type_param_mapper;
 mod mock_struct_implementer;
 mod trait_implementer;
 mod behaviour;
+mod call_history;
+mod capture;
+mod context;
+mod describe;
+mod field_matchers;
+mod future;
+mod matchers;
+mod pattern_matcher;
+mod return_kind;
+mod verification;
 
 use syn;
 use quote;
 
+pub use ::generate::behaviour::{implement_create_behaviour_guard, implement_statement_fn};
+pub use ::generate::capture::can_capture;
+
 use ::generate::binding_implementer::*;
 use ::generate::behaviour::*;
+use ::generate::future::*;
+use ::generate::matchers::*;
+use ::generate::return_kind::{ReturnKind, implement_default_value};
+use ::generate::call_history::*;
+use ::generate::context::*;
+use ::generate::describe::*;
+use ::generate::verification::*;
 use ::generate::type_param_mapper::*;
 use ::generate::mock_struct_implementer::*;
 use ::generate::trait_implementer::*;
@@ -38,13 +58,21 @@ pub fn handle_generate_mocks() -> Vec<quote::Tokens> {
 
     let mut tokens = implement_bindings(&bindings);
     tokens.extend(implement_argmatcher());
+    tokens.extend(implement_builtin_matchers());
+    tokens.extend(implement_matcher_description());
     tokens.extend(implement_given_behaviour());
     tokens.extend(implement_expect_behaviour());
+    tokens.extend(implement_behaviour_guard());
+    tokens.extend(implement_call_record());
+    tokens.extend(implement_verification_report());
+    tokens.extend(implement_mock_recorder());
+    tokens.extend(implement_mock_context());
+    tokens.extend(implement_mock_future());
+    tokens.extend(implement_default_value());
 
     for requested_mock in requested_mocks.iter() {
         let inst_traits = requested_mock.traits.iter().map(|trait_ty| create_instantiated_traits(trait_ty, &mockable_traits)).collect::<Vec<_>>();
-        tokens.extend(handle_generate_mock(requested_mock.maybe_type_name.as_ref().expect("Internal error: requested mock has no type name"),
-                                           &requested_mock.attributes,
+        tokens.extend(handle_generate_mock(requested_mock,
                                            &inst_traits,
                                            &given_statements,
                                            &expect_statements));
@@ -60,11 +88,15 @@ pub fn handle_generate_mocks() -> Vec<quote::Tokens> {
 
 fn create_instantiated_traits(trait_path: &syn::Path, mockable_traits: &MockableTraits)
                                -> InstantiatedTrait {
-    let trait_info = mockable_traits
-                     .get(&strip_generics(trait_path.clone()))
-                     .expect(&format!("All mocked traits must be defined using 'mockable!': `{}` not found in {}",
-                                      quote!(#trait_path).to_string(),
-                                      mockable_traits.keys().map(|k| quote!(#k).to_string()).collect::<Vec<_>>().join(", ")));
+    find_instantiated_trait(trait_path, mockable_traits)
+        .unwrap_or_else(|| panic!("All mocked traits must be defined using 'mockable!': `{}` not found in {}",
+                                  quote!(#trait_path),
+                                  mockable_traits.keys().map(|k| quote!(#k).to_string()).collect::<Vec<_>>().join(", ")))
+}
+
+/// Instantiates the mockable trait used as `trait_path`, or returns `None` if the trait is unknown.
+fn find_instantiated_trait(trait_path: &syn::Path, mockable_traits: &MockableTraits) -> Option<InstantiatedTrait> {
+    let trait_info = mockable_traits.get(&strip_generics(trait_path.clone()))?;
     let mut mapper = TypeParamMapper::new();
     {
         let generics: &syn::Generics = &trait_info.generics;
@@ -75,11 +107,11 @@ fn create_instantiated_traits(trait_path: &syn::Path, mockable_traits: &Mockable
         }
     }
 
-    InstantiatedTrait {
+    Some(InstantiatedTrait {
         trait_ty: trait_path.clone(),
         info: trait_info.clone(),
         mapper: mapper
-    }
+    })
 }
 
 fn strip_generics(mut path_with_generics: syn::Path) -> syn::Path {
@@ -100,16 +132,17 @@ fn strip_generics(mut path_with_generics: syn::Path) -> syn::Path {
 /// All mock types requested by a `new_mock!` invocations are generated with this function.
 ///
 /// # Paramters
-/// * `mock_type_name` - The name of the generated mock type
-/// * `trait_tys` - The (generic) trait types which are requested for the mock
-fn handle_generate_mock(mock_type_name: &syn::Ident,
-                        attributes: &[syn::Attribute],
+/// * `requested_mock` - The mock as requested by `new_mock!`, e.g., its name, attributes, and fake methods
+/// * `requested_traits` - The instantiated (generic) traits which are requested for the mock
+fn handle_generate_mock(requested_mock: &RequestedMock,
                         requested_traits: &[InstantiatedTrait],
                         given_statements: &GivenStatements,
                         expect_statements: &ExpectStatements
                        ) -> Vec<quote::Tokens> {
-    let mock_implementer = MockStructImplementer::for_(mock_type_name, attributes);
+    let mock_type_name = requested_mock.maybe_type_name.as_ref().expect("Internal error: requested mock has no type name");
+    let mock_implementer = MockStructImplementer::for_(mock_type_name, requested_mock);
     let mut mock = mock_implementer.implement();
+    mock.push(implement_fake_methods(mock_type_name, &requested_mock.fake_methods, requested_traits));
 
     let empty_given = Vec::new();
     let empty_expect = Vec::new();
@@ -118,16 +151,52 @@ fn handle_generate_mock(mock_type_name: &syn::Ident,
                                                          .unwrap_or(&empty_given);
         let expect_statements_for_trait = expect_statements.get(&inst_trait.trait_ty)
                                                            .unwrap_or(&empty_expect);
-        mock.push(TraitImplementer::for_(mock_type_name,
-                                         inst_trait,
-                                         given_statements_for_trait,
-                                         expect_statements_for_trait
-                                    ).implement());
+        let trait_implementer = TraitImplementer::for_(mock_type_name,
+                                                       inst_trait,
+                                                       requested_mock,
+                                                       given_statements_for_trait,
+                                                       expect_statements_for_trait);
+        mock.push(trait_implementer.implement());
+        mock.push(trait_implementer.implement_capturing_statements());
     }
 
     mock
 }
 
+/// Generates the methods of a fake implementation stated with `with_impl` as inherent methods of the mock.
+///
+/// The methods are renamed, see `fake_method_name`, so calls of the mocked methods are still dispatched to the traits.
+///
+/// # Panics
+/// If a method is not a method of exactly one of the requested traits.
+fn implement_fake_methods(mock_type_name: &syn::Ident, fake_methods: &[syn::ImplItem], requested_traits: &[InstantiatedTrait]) -> quote::Tokens {
+    let methods = fake_methods.iter().map(|method| {
+        let num_traits_with_method = requested_traits.iter().filter(|inst_trait| inst_trait.info.items.iter().any(|item| {
+            item.ident == method.ident && matches!(item.node, syn::TraitItemKind::Method(..))
+        })).count();
+        match num_traits_with_method {
+            0 => panic!("The fake implementation of `{}` is not a method of the mocked traits.", method.ident),
+            1 => {},
+            _ => panic!("The fake implementation of `{}` is ambiguous as the method is part of several mocked traits.", method.ident)
+        }
+
+        let mut fake_method = method.clone();
+        fake_method.ident = fake_method_name(&method.ident);
+        fake_method
+    }).collect::<Vec<_>>();
+
+    quote! {
+        impl #mock_type_name {
+            #(#methods)*
+        }
+    }
+}
+
+/// The name of the mock's inherent method implementing the fake of `method`.
+fn fake_method_name(method: &syn::Ident) -> syn::Ident {
+    syn::Ident::from(format!("fake_{}", method))
+}
+
 fn extract_parameterized_types_from_trait_use(trait_ty: &syn::Path) -> Vec<syn::Ty> {
     match trait_ty.segments[0].parameters {
         syn::PathParameters::AngleBracketed(ref data) => data.types.clone(),
@@ -139,6 +208,14 @@ fn implement_argmatcher() -> Vec<quote::Tokens> {
     let argmatcher_trait = quote! {
         pub trait ArgMatcher<'a, T:'a> {
             fn match_args(&self, actual: &'a T) -> bool;
+
+            /// Describes the values accepted by the matcher, e.g., `less than 2`.
+            ///
+            /// Matchers without a description are represented by their source in failure messages.
+            fn describe(&self) -> Option<String> { None }
+
+            /// Explains why `actual` is not matched, e.g., which field of a struct differs.
+            fn explain_mismatch(&self, actual: &'a T) -> Option<String> { None }
         }
     };
 
@@ -153,10 +230,21 @@ fn implement_argmatcher() -> Vec<quote::Tokens> {
 
     if cfg!(feature = "galvanic_assert_integration") {
         let matcher_argmatcher_impl = quote! {
+            /// `galvanic-assert` matchers only name themselves when checking a value, so they are described by their source.
             impl<'a, T:'a> ArgMatcher<'a,T> for Box<::galvanic_assert::Matcher<'a, T> + 'a> {
                 fn match_args(&self, actual: &'a T) -> bool {
                     self.check(actual).into()
                 }
+
+                fn explain_mismatch(&self, actual: &'a T) -> Option<String> {
+                    match self.check(actual) {
+                        ::galvanic_assert::MatchResult::Matched { .. } => None,
+                        ::galvanic_assert::MatchResult::Failed { name, reason } => {
+                            let reason = reason.lines().map(|line| line.trim()).collect::<Vec<_>>().join(", ");
+                            Some(format!("{}: {}", name, reason))
+                        }
+                    }
+                }
             }
         };
 
@@ -164,11 +252,21 @@ fn implement_argmatcher() -> Vec<quote::Tokens> {
     } else { vec![argmatcher_trait, argmatcher_impl] }
 }
 
+/// Checks if a method argument is the receiver, i.e., `self`, `&self`, or `self` with an explicit type like `self: Box<Self>`.
+pub fn is_receiver(arg: &syn::FnArg) -> bool {
+    match arg {
+        &syn::FnArg::SelfRef(..) | &syn::FnArg::SelfValue(..) => true,
+        &syn::FnArg::Captured(syn::Pat::Ident(_, ref ident, None), _) => ident == "self",
+        _ => false
+    }
+}
+
 pub fn typed_arguments_for_method_sig(signature: &syn::MethodSig, mapper: &TypeParamMapper) -> Vec<quote::Tokens> {
     let mut arg_idx = 1;
     signature.decl.inputs.iter().map(|arg| {
         let arg_name = syn::Ident::from(format!("arg{}", arg_idx));
         match arg {
+            arg if is_receiver(arg) => quote!(#arg),
             &syn::FnArg::Captured(_, ref ty) => {
                 let inst_ty = mapper.instantiate_from_ty(ty);
                 arg_idx += 1;
@@ -178,7 +276,7 @@ pub fn typed_arguments_for_method_sig(signature: &syn::MethodSig, mapper: &TypeP
                 let inst_ty = mapper.instantiate_from_ty(ty);
                 arg_idx += 1;
                 quote!(#arg_name: #inst_ty)
-            }
+            },
             _ => quote!(#arg)
     }}).collect::<Vec<_>>()
 }
@@ -190,3 +288,21 @@ pub struct InstantiatedTrait {
     info: TraitInfo,
     mapper: TypeParamMapper,
 }
+
+impl InstantiatedTrait {
+    /// Determines how values are returned by `method` and the instantiated type of the returned values.
+    ///
+    /// For `async` methods the type is the future's `Output`.
+    fn return_of(&self, method: &syn::Ident, signature: &syn::MethodSig) -> (ReturnKind, syn::Ty) {
+        if self.info.async_methods.contains(method) {
+            let output = match signature.decl.output {
+                syn::FunctionRetTy::Ty(ref ty) => self.mapper.instantiate_from_ty(ty),
+                syn::FunctionRetTy::Default => syn::Ty::Tup(Vec::new())
+            };
+            (ReturnKind::Future(FutureReturn::Impl, output.clone()), output)
+        } else if let syn::FunctionRetTy::Ty(ref ty) = signature.decl.output {
+            let inst_ty = self.mapper.instantiate_from_ty(ty);
+            (ReturnKind::for_return_ty(&inst_ty), inst_ty)
+        } else { (ReturnKind::Value, syn::Ty::Tup(Vec::new())) }
+    }
+}
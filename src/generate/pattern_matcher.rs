@@ -0,0 +1,149 @@
+/* Copyright 2017 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use syn;
+use quote;
+
+/// An argument matcher written as a Rust pattern with an optional guard: `pat!(Pattern if guard)`.
+///
+/// The matcher is expanded into a `match` on the argument evaluating to the pattern's bindings if it matches.
+/// The argument is matched by value so `Copy` values can be bound directly, other values must be bound with `ref`.
+pub struct PatternMatcher {
+    pattern: String,
+    guard: Option<String>,
+    bindings: Vec<syn::Ident>
+}
+
+impl PatternMatcher {
+    /// Returns the pattern matcher if the argument matcher `expr` is a `pat!` invocation.
+    ///
+    /// # Panics
+    /// If `pat!` is used within another matcher.
+    pub fn detect(expr: &syn::Expr) -> Option<PatternMatcher> {
+        if let syn::ExprKind::Mac(ref mac) = expr.node {
+            if is_pat_macro(mac) {
+                return Some(PatternMatcher::parse(&mac.tts));
+            }
+        }
+
+        let mut nested = NestedPatternMatchers { found: false };
+        syn::visit::Visitor::visit_expr(&mut nested, expr);
+        if nested.found {
+            panic!("pat! can only be used as an argument matcher itself but not within other matchers.");
+        }
+        None
+    }
+
+    fn parse(tts: &[syn::TokenTree]) -> PatternMatcher {
+        let tts = match tts {
+            &[syn::TokenTree::Delimited(ref delimited)] => &delimited.tts,
+            _ => panic!("pat! expects a pattern with an optional guard: pat!(Pattern if guard)")
+        };
+        let is_if = |tt: &syn::TokenTree| tt == &syn::TokenTree::Token(syn::Token::Ident(syn::Ident::from("if")));
+        let (pattern, guard) = match tts.iter().position(is_if) {
+            Some(pos) => (&tts[..pos], Some(&tts[pos + 1..])),
+            None => (&tts[..], None)
+        };
+        if pattern.is_empty() {
+            panic!("pat! expects a pattern with an optional guard: pat!(Pattern if guard)");
+        }
+
+        PatternMatcher {
+            pattern: quote!(#(#pattern)*).to_string(),
+            guard: guard.map(|guard| quote!(#(#guard)*).to_string()),
+            bindings: collect_bindings(pattern)
+        }
+    }
+
+    /// Describes the pattern for failure messages.
+    pub fn describe(&self) -> String {
+        match self.guard {
+            Some(ref guard) => format!("matching {} if {}", self.pattern, guard),
+            None => format!("matching {}", self.pattern)
+        }
+    }
+
+    /// Creates an expression matching the place expression `value` against the pattern.
+    ///
+    /// The expression evaluates to `Some` tuple of the pattern's bindings if `value` matches, see `implement_bindings`.
+    pub fn implement_match(&self, value: &str) -> quote::Tokens {
+        let bindings = self.implement_bindings();
+        let mut tokens = quote::Tokens::new();
+        tokens.append(format!("match {} {{ {} {} => Some({}), _ => None }}",
+                              value,
+                              self.pattern,
+                              self.guard.as_ref().map_or(String::new(), |guard| format!("if {}", guard)),
+                              bindings));
+        tokens
+    }
+
+    /// Creates the tuple of the pattern's bindings, usable both as expression and as pattern.
+    pub fn implement_bindings(&self) -> quote::Tokens {
+        let bindings = &self.bindings;
+        quote!( (#(#bindings,)*) )
+    }
+}
+
+fn is_pat_macro(mac: &syn::Mac) -> bool {
+    mac.path == syn::Path::from("pat")
+}
+
+/// Finds `pat!` invocations within an argument matcher.
+struct NestedPatternMatchers {
+    found: bool
+}
+
+impl syn::visit::Visitor for NestedPatternMatchers {
+    fn visit_mac(&mut self, mac: &syn::Mac) {
+        self.found |= is_pat_macro(mac);
+        syn::visit::walk_mac(self, mac);
+    }
+}
+
+/// Collects the variables bound by the pattern `tts`.
+///
+/// Alternatives bind the same variables so only the first one is considered.
+fn collect_bindings(tts: &[syn::TokenTree]) -> Vec<syn::Ident> {
+    let alternative = tts.split(|tt| tt == &syn::TokenTree::Token(syn::Token::BinOp(syn::BinOpToken::Or)))
+                         .next()
+                         .unwrap_or(tts);
+    let source = quote!(#(#alternative)*).to_string();
+    let pattern = match syn::parse::pat(&source) {
+        syn::parse::IResult::Done(rest, ref pattern) if rest.trim().is_empty() => pattern.clone(),
+        _ => panic!("pat! expects a pattern with an optional guard instead of: {}", source)
+    };
+
+    let mut bindings = PatternBindings { bindings: Vec::new() };
+    syn::visit::Visitor::visit_pat(&mut bindings, &pattern);
+    bindings.bindings
+}
+
+struct PatternBindings {
+    bindings: Vec<syn::Ident>
+}
+
+impl syn::visit::Visitor for PatternBindings {
+    fn visit_pat(&mut self, pat: &syn::Pat) {
+        if let syn::Pat::Ident(ref mode, ref ident, ref subpattern) = *pat {
+            // identifiers may also name unit variants or constants which are capitalized by convention
+            let is_binding = subpattern.is_some()
+                || mode != &syn::BindingMode::ByValue(syn::Mutability::Immutable)
+                || ident.as_ref().starts_with(|c: char| c.is_lowercase() || c == '_');
+            if is_binding {
+                self.bindings.push(ident.clone());
+            }
+        }
+        syn::visit::walk_pat(self, pat);
+    }
+}
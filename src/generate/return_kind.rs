@@ -0,0 +1,210 @@
+/* Copyright 2017 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use syn;
+use quote;
+
+use super::future::FutureReturn;
+
+/// Describes how the values of given behaviours are converted to the return type of a mocked method.
+#[derive(Clone, Debug)]
+pub enum ReturnKind {
+    /// The value is returned as is.
+    Value,
+    /// The value is wrapped in a `MockFuture` producing the stated `Output` type.
+    Future(FutureReturn, syn::Ty),
+    /// An `impl Iterator<Item = T>` is returned, the value is boxed to unify the types of all behaviours.
+    ImplIterator(syn::Ty),
+    /// A `Box<dyn Iterator<Item = T>>` with the stated type is returned.
+    BoxedIterator(syn::Ty),
+}
+
+impl ReturnKind {
+    /// Determines the kind of a method's (instantiated) return type.
+    pub fn for_return_ty(ty: &syn::Ty) -> ReturnKind {
+        if let Some((future_return, output)) = FutureReturn::detect(ty) {
+            return ReturnKind::Future(future_return, output);
+        }
+
+        match *ty {
+            syn::Ty::ImplTrait(ref bounds) => match item_of_bounds(bounds) {
+                Some(item) => ReturnKind::ImplIterator(item),
+                None => ReturnKind::Value
+            },
+            syn::Ty::Path(None, ..) => match item_of_boxed_iterator(ty) {
+                Some(..) => ReturnKind::BoxedIterator(ty.clone()),
+                None => ReturnKind::Value
+            },
+            _ => ReturnKind::Value
+        }
+    }
+
+    /// The type of the items if an iterator is returned.
+    pub fn iterator_item(&self) -> Option<syn::Ty> {
+        match *self {
+            ReturnKind::ImplIterator(ref item) => Some(item.clone()),
+            ReturnKind::BoxedIterator(ref ty) => item_of_boxed_iterator(ty),
+            _ => None
+        }
+    }
+
+    /// The type of the value stored by the generated method before it is returned.
+    pub fn value_ty(&self) -> quote::Tokens {
+        match *self {
+            ReturnKind::Value => quote!{ _ },
+            ReturnKind::Future(_, ref output) => quote!{ MockFuture<#output> },
+            ReturnKind::ImplIterator(ref item) => quote!{ std::boxed::Box<dyn Iterator<Item = #item>> },
+            ReturnKind::BoxedIterator(ref ty) => quote!{ #ty },
+        }
+    }
+
+    /// Converts the stored value in the variable `value` to the method's return type.
+    pub fn wrap(&self, value: &syn::Ident) -> quote::Tokens {
+        match *self {
+            ReturnKind::Future(ref future_return, _) => future_return.wrap(value),
+            _ => quote!{ #value }
+        }
+    }
+
+    /// Converts the value of a `then_return`/`then_return_from` expression to the stored value.
+    pub fn convert(&self, expr: quote::Tokens) -> quote::Tokens {
+        match *self {
+            ReturnKind::Future(..) => quote!{ MockFuture::ready(#expr) },
+            ReturnKind::ImplIterator(..) => quote!{ std::boxed::Box::new(#expr) },
+            _ => expr
+        }
+    }
+
+    /// Converts the collection of a `then_return_iter` expression to the stored value.
+    pub fn convert_iter(&self, expr: quote::Tokens) -> quote::Tokens {
+        let into_iter = quote!{ std::iter::IntoIterator::into_iter(#expr) };
+        match self {
+            &ReturnKind::BoxedIterator(..) => quote!{ std::boxed::Box::new(#into_iter) },
+            _ => self.convert(into_iter)
+        }
+    }
+
+    /// Creates an expression evaluating to `Some` default value for the method's return type `return_ty`,
+    /// or `None` if the type does not implement `Default`.
+    ///
+    /// Returns `None` if the return type cannot be named, e.g., for `impl Trait` return types other than iterators.
+    pub fn default_value(&self, return_ty: &syn::Ty) -> Option<quote::Tokens> {
+        let default_of = |ty: &syn::Ty| quote!{ (&DefaultValue::<#ty>::new()).default_value() };
+        match *self {
+            ReturnKind::Value => match return_ty {
+                &syn::Ty::ImplTrait(..) => None,
+                ty => Some(default_of(ty))
+            },
+            ReturnKind::Future(_, ref output) => {
+                let default_output = default_of(output);
+                Some(quote!{ #default_output.map(MockFuture::ready) })
+            },
+            ReturnKind::ImplIterator(ref item) => Some(quote!{ Some(std::boxed::Box::new(std::iter::empty::<#item>())) }),
+            ReturnKind::BoxedIterator(..) => Some(quote!{ Some(std::boxed::Box::new(std::iter::empty())) }),
+        }
+    }
+}
+
+/// Generates the types for determining default return values of nice mocks.
+///
+/// The selection uses auto-ref based dispatch: `(&DefaultValue::<T>::new()).default_value()` resolves to
+/// `DefaultValueOf` if `T` implements `Default`, to `DefaultResultOf` if `T` is a `Result` whose `Ok` type
+/// implements `Default`, and to `NoDefaultValue` otherwise.
+pub fn implement_default_value() -> Vec<quote::Tokens> {
+    let default_value = quote! {
+        pub struct DefaultValue<T>(std::marker::PhantomData<T>);
+
+        #[allow(dead_code)]
+        impl<T> DefaultValue<T> {
+            pub fn new() -> Self {
+                DefaultValue(std::marker::PhantomData)
+            }
+        }
+
+        pub trait DefaultValueOf<T> {
+            fn default_value(&self) -> Option<T>;
+        }
+
+        impl<T: Default> DefaultValueOf<T> for DefaultValue<T> {
+            fn default_value(&self) -> Option<T> {
+                Some(T::default())
+            }
+        }
+
+        pub trait DefaultResultOf<T> {
+            fn default_value(&self) -> Option<T>;
+        }
+
+        impl<T: Default, E> DefaultResultOf<Result<T, E>> for DefaultValue<Result<T, E>> {
+            fn default_value(&self) -> Option<Result<T, E>> {
+                Some(Ok(T::default()))
+            }
+        }
+
+        pub trait NoDefaultValue<T> {
+            fn default_value(&self) -> Option<T>;
+        }
+
+        impl<'a, T> NoDefaultValue<T> for &'a DefaultValue<T> {
+            fn default_value(&self) -> Option<T> {
+                None
+            }
+        }
+    };
+
+    vec![default_value]
+}
+
+fn item_of_boxed_iterator(ty: &syn::Ty) -> Option<syn::Ty> {
+    match ty {
+        &syn::Ty::Path(None, ref path) => {
+            let segment = path.segments.last().expect("A type path without segment is not valid.");
+            match segment.parameters {
+                syn::PathParameters::AngleBracketed(ref data) if segment.ident == "Box" && data.types.len() == 1 =>
+                    data.types.first().and_then(item_of_trait_object),
+                _ => None
+            }
+        },
+        _ => None
+    }
+}
+
+fn item_of_trait_object(ty: &syn::Ty) -> Option<syn::Ty> {
+    match *ty {
+        syn::Ty::Path(None, ref path) => item_of_iterator_path(path),
+        syn::Ty::TraitObject(ref bounds) => item_of_bounds(bounds),
+        _ => None
+    }
+}
+
+fn item_of_bounds(bounds: &[syn::TyParamBound]) -> Option<syn::Ty> {
+    bounds.iter().filter_map(|bound| match *bound {
+        syn::TyParamBound::Trait(ref poly_trait_ref, _) => item_of_iterator_path(&poly_trait_ref.trait_ref),
+        _ => None
+    }).next()
+}
+
+fn item_of_iterator_path(path: &syn::Path) -> Option<syn::Ty> {
+    let segment = path.segments.last().expect("A type path without segment is not valid.");
+    if segment.ident != "Iterator" {
+        return None;
+    }
+
+    match segment.parameters {
+        syn::PathParameters::AngleBracketed(ref data) => data.bindings.iter()
+                                                             .find(|binding| binding.ident == "Item")
+                                                             .map(|binding| binding.ty.clone()),
+        _ => None
+    }
+}
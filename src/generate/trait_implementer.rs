@@ -19,13 +19,16 @@ use quote::ToTokens;
 use std;
 
 use super::InstantiatedTrait;
-use super::typed_arguments_for_method_sig;
+use super::{is_receiver, typed_arguments_for_method_sig, fake_method_name};
 use super::behaviour::*;
+use super::capture::CapturingMethod;
+use super::return_kind::ReturnKind;
 use data::*;
 
 pub struct TraitImplementer<'a> {
     mock_type_name: &'a syn::Ident,
     instantiated_trait: &'a InstantiatedTrait,
+    requested_mock: &'a RequestedMock,
     given_statements: &'a [GivenStatement],
     expect_statements: &'a [ExpectStatement]
 }
@@ -33,12 +36,14 @@ pub struct TraitImplementer<'a> {
 impl<'a> TraitImplementer<'a> {
     pub fn for_(mock_type_name: &'a syn::Ident,
                 instantiated_trait: &'a InstantiatedTrait,
+                requested_mock: &'a RequestedMock,
                 given_statements_for_trait: &'a [GivenStatement],
                 expect_statements_for_trait: &'a [ExpectStatement]
                ) -> TraitImplementer<'a>  {
         TraitImplementer {
-            mock_type_name: mock_type_name,
-            instantiated_trait: instantiated_trait,
+            mock_type_name,
+            instantiated_trait,
+            requested_mock,
             given_statements: given_statements_for_trait,
             expect_statements: expect_statements_for_trait
         }
@@ -66,6 +71,43 @@ impl<'a> TraitImplementer<'a> {
         }
     }
 
+    /// Generates the mock's methods for the statements of the trait which are evaluated by closures capturing their environment.
+    pub fn implement_capturing_statements(&self) -> quote::Tokens {
+        let mut methods = Vec::new();
+        for item in self.instantiated_trait.info.items.iter() {
+            let given_statements = self.given_statements.iter()
+                                       .filter(|stmt| stmt.method == item.ident && stmt.captures)
+                                       .collect::<Vec<_>>();
+            let expect_only_statements = self.expect_statements.iter()
+                                             .filter(|stmt| stmt.method == item.ident && stmt.captures && !self.is_combined(stmt))
+                                             .collect::<Vec<_>>();
+            if given_statements.is_empty() && expect_only_statements.is_empty() {
+                continue;
+            }
+
+            let capturing_method = self.capturing_method(&item.ident);
+            methods.extend(given_statements.into_iter().map(|stmt| capturing_method.implement_capture_method(stmt.stmt_id, Some(&stmt.return_stmt))));
+            methods.extend(expect_only_statements.into_iter().map(|stmt| capturing_method.implement_capture_method(stmt.stmt_id, None)));
+        }
+
+        let mock_type_name = self.mock_type_name;
+        quote! {
+            impl #mock_type_name {
+                #(#methods)*
+            }
+        }
+    }
+
+    fn capturing_method(&self, method: &syn::Ident) -> CapturingMethod {
+        CapturingMethod::for_method(self.instantiated_trait, method)
+            .expect("galvanic_mock internal error: the statements of the method cannot be captured")
+    }
+
+    /// Checks if the expect statement is part of a combined given-and-expect statement.
+    fn is_combined(&self, statement: &ExpectStatement) -> bool {
+        self.given_statements.iter().any(|given_stmt| given_stmt.stmt_id == statement.stmt_id)
+    }
+
     fn extract_associated_types(trait_ty: &mut syn::Path, lifetimes: Vec<syn::Lifetime>) -> Vec<syn::TypeBinding> {
         let ty = trait_ty.segments.last_mut().expect("A type path without segment is not valid.");
         if let &mut syn::PathParameters::AngleBracketed(ref mut params) = &mut ty.parameters {
@@ -77,11 +119,8 @@ impl<'a> TraitImplementer<'a> {
     fn implement_mocked_method(&self, item: &syn::TraitItem) -> Option<quote::Tokens> {
         let mut tokens = quote::Tokens::new();
         if let &syn::TraitItemKind::Method(ref signature, _) = &item.node {
-            signature.decl.inputs.iter().find(|arg| match arg {
-                &&syn::FnArg::SelfValue(..) => true,
-                &&syn::FnArg::SelfRef(..) => true,
-                _ => false
-            }).expect("Static methods are not supported yet.");
+            signature.decl.inputs.iter().find(|arg| is_receiver(arg))
+                                        .expect("Static methods are not supported yet.");
 
             let func_name = &item.ident;
 
@@ -98,9 +137,12 @@ impl<'a> TraitImplementer<'a> {
             tokens.append_separated(&args, ",");
 
             tokens.append(")");
-            if let syn::FunctionRetTy::Ty(ref ty) = signature.decl.output {
+            let (return_kind, return_ty) = self.instantiated_trait.return_of(func_name, signature);
+            if self.instantiated_trait.info.async_methods.contains(func_name) {
+                tokens.append(quote!(-> impl std::future::Future<Output = #return_ty>));
+            } else if let syn::FunctionRetTy::Ty(..) = signature.decl.output {
                 tokens.append("->");
-                self.instantiated_trait.mapper.instantiate_from_ty(ty).to_tokens(&mut tokens);
+                return_ty.to_tokens(&mut tokens);
             }
             signature.generics.where_clause.to_tokens(&mut tokens);
             tokens.append("{");
@@ -109,46 +151,138 @@ impl<'a> TraitImplementer<'a> {
 
             let given_behaviour_impls = self.given_statements.iter()
                                             .filter(|stmt| stmt.method == item.ident)
-                                            .map(|stmt| implement_given_behaviour_matcher(stmt))
+                                            .map(|stmt| if stmt.captures {
+                                                let statement_fn_ty = self.capturing_method(func_name).statement_fn_ty(true);
+                                                implement_captured_given_behaviour_matcher(stmt, &statement_fn_ty)
+                                            } else { implement_given_behaviour_matcher(stmt, &return_kind) })
+                                            .collect::<Vec<_>>();
+            let exhausted_behaviour_impls = self.given_statements.iter()
+                                            .filter(|stmt| stmt.method == item.ident)
+                                            .map(|stmt| if stmt.captures {
+                                                let statement_fn_ty = self.capturing_method(func_name).statement_fn_ty(true);
+                                                implement_captured_exhausted_behaviour_matcher(stmt, &statement_fn_ty)
+                                            } else { implement_exhausted_behaviour_matcher(stmt) })
                                             .collect::<Vec<_>>();
             let expect_behaviour_impls = self.expect_statements.iter()
                                             .filter(|stmt| stmt.method == item.ident)
-                                            .map(|stmt| implement_expect_behaviour_matcher(stmt))
+                                            .map(|stmt| if stmt.captures {
+                                                let statement_fn_ty = self.capturing_method(func_name).statement_fn_ty(self.is_combined(stmt));
+                                                implement_captured_expect_behaviour_matcher(stmt, &statement_fn_ty)
+                                            } else { implement_expect_behaviour_matcher(stmt) })
                                             .collect::<Vec<_>>();
 
             let trait_ty = &self.instantiated_trait.trait_ty;
             let trait_name = quote!(#trait_ty).to_string();
             let method_name = func_name.to_string();
+            let arg_reprs = (0..args.len()).map(|idx| {
+                let mut arg_repr = quote::Tokens::new();
+                arg_repr.append(format!("(&DebugArg(&curried_args.{})).debug_repr()", idx));
+                arg_repr
+            }).collect::<Vec<_>>();
+            let value = syn::Ident::from("value");
+            let return_value_ty = return_kind.value_ty();
+            let return_value = return_kind.wrap(&value);
+            let is_recordable = self.requested_mock.fixture.is_some() && Self::is_recordable(signature, &return_kind, &return_ty);
+            let (fixture_args, record_value, replay) = if is_recordable {
+                self.implement_fixture(&trait_name, &method_name, args.len(), &return_ty)
+            } else { (quote::Tokens::new(), quote::Tokens::new(), quote::Tokens::new()) };
+            let spy_call = self.implement_spy_call(func_name, signature, args.len(), &return_kind, &record_value);
+            let has_spy_statement = self.given_statements.iter()
+                                        .any(|stmt| stmt.method == item.ident && stmt.return_stmt == Return::FromSpy);
+            let (declare_spied_call, spied_call) = if has_spy_statement {
+                let spy_call = spy_call.as_ref().expect("`then_spy_on_object` requires a mock with a `#[spy_on(Type)]` attribute and a method with a receiver not returning a future.");
+                (quote!{ let mut is_spied_call = false; }, quote!{
+                    if is_spied_call {
+                        #spy_call
+                    }
+                })
+            } else { (quote::Tokens::new(), quote::Tokens::new()) };
+            let spy_fallback = match spy_call {
+                Some(ref spy_call) => quote!{
+                    if self.spied_object.borrow().is_some() {
+                        #spy_call
+                    }
+                },
+                None => quote::Tokens::new()
+            };
+            let fake_call = if self.requested_mock.fake_methods.iter().any(|method| method.ident == *func_name) {
+                let fake_method = fake_method_name(func_name);
+                let fake_args = (0..args.len()).map(|idx| {
+                    let mut fake_arg = quote::Tokens::new();
+                    fake_arg.append(format!("curried_args.{}", idx));
+                    fake_arg
+                }).collect::<Vec<_>>();
+                let fake_value = return_kind.convert(quote!{ self.#fake_method(#(#fake_args),*) });
+                quote!{
+                    let value: #return_value_ty = #fake_value;
+                    #record_value
+                    return #return_value;
+                }
+            } else { quote::Tokens::new() };
+            let nice_default = match return_kind.default_value(&return_ty) {
+                Some(ref default_value) if self.requested_mock.is_nice => quote!{
+                    if let Some(value) = #default_value {
+                        #record_value
+                        return #return_value;
+                    }
+                },
+                _ => quote::Tokens::new()
+            };
 
             tokens.append(quote!{
                 let curried_args = (#(#args,)*);
-                for behaviour in self.expect_behaviours.borrow_mut().entry((#trait_name, #method_name)).or_insert_with(|| Vec::new()).iter() {
+                #fixture_args
+                let call_args: Vec<String> = vec![#(#arg_reprs),*];
+                self.run_call_hooks(#trait_name, #method_name, &call_args);
+
+                let mut matched_expect_behaviour = None;
+                let mut nearest_expect_behaviour = None;
+                for behaviour in self.recorder.expect_behaviours.borrow_mut().entry((#trait_name, #method_name)).or_insert_with(|| Vec::new()).iter() {
                     #(
                         #expect_behaviour_impls
                     )*
                 }
-
-                let mut maybe_remove_idx = None;
-                let mut return_value = None;
-                let mut all_given_behaviours_ref = self.given_behaviours.borrow_mut();
-                let given_behaviours = all_given_behaviours_ref.entry((#trait_name, #method_name)).or_insert_with(|| Vec::new());
-                for (idx, behaviour) in given_behaviours.iter().enumerate() {
+            });
+            tokens.append(quote!{
+                self.recorder.record_call(#trait_name, #method_name, call_args.clone(), matched_expect_behaviour, nearest_expect_behaviour);
+            });
+            tokens.append(quote!{
+                let mut return_value: Option<#return_value_ty> = None;
+                let mut unmatched_given_behaviours: Vec<String> = Vec::new();
+                #declare_spied_call
+                for behaviour in self.order_given_behaviours(self.given_behaviours.borrow_mut().entry((#trait_name, #method_name)).or_insert_with(|| Vec::new())) {
                     #(
                         #given_behaviour_impls
                     )*
                 }
+                self.retire_exhausted_given_behaviours(#trait_name, #method_name);
 
-                if let Some(idx) = maybe_remove_idx {
-                    if (&given_behaviours[idx] as &GivenBehaviour).is_saturated() {
-                        given_behaviours.remove(idx);
-                    }
+                if let Some(value) = return_value {
+                    #record_value
+                    return #return_value;
                 }
 
-                if let Some(value) = return_value {
-                    return value;
+                // the exhausted behaviours are only evaluated to explain the unmatched call
+                let mut exhausted_given_behaviours: Vec<String> = Vec::new();
+                if let Some(behaviours) = self.exhausted_given_behaviours.borrow().get(&(#trait_name, #method_name)) {
+                    for behaviour in behaviours.iter() {
+                        #(
+                            #exhausted_behaviour_impls
+                        )*
+                    }
                 }
-                panic!("No matching given! statement found among the remaining ones: {}",
-                    given_behaviours.iter().map(|behaviour| format!("\n\t{}", behaviour.describe())).collect::<String>()
+                #spied_call
+                #replay
+                #spy_fallback
+                #fake_call
+                #nice_default
+                panic!("No matching given! statement found for call {}({}) among the remaining ones:{}{}",
+                    #method_name, call_args.join(", "),
+                    unmatched_given_behaviours.iter().map(|behaviour| format!("\n\t{}", behaviour)).collect::<String>(),
+                    if exhausted_given_behaviours.is_empty() { String::new() } else {
+                        format!("\nExhausted given! statements which would have matched:{}",
+                                exhausted_given_behaviours.iter().map(|behaviour| format!("\n\t{}", behaviour)).collect::<String>())
+                    }
                 )
             });
 
@@ -158,10 +292,104 @@ impl<'a> TraitImplementer<'a> {
         None
     }
 
+    /// Checks if the calls of a method can be recorded to or replayed from a fixture.
+    ///
+    /// This requires a non-generic method returning a value which does not borrow.
+    fn is_recordable(signature: &syn::MethodSig, return_kind: &ReturnKind, return_ty: &syn::Ty) -> bool {
+        let is_plain_value = match return_kind {
+            &ReturnKind::Value => !quote!(#return_ty).to_string().split_whitespace()
+                                                     .any(|token| token == "Self" || token == "impl" || token.starts_with('&') || token.starts_with('\'')),
+            _ => false
+        };
+        signature.generics.ty_params.is_empty() && signature.generics.lifetimes.is_empty() && is_plain_value
+    }
+
+    /// Generates the serialization of the arguments, the recording of a returned `value`, and the replaying of a call.
+    fn implement_fixture(&self, trait_name: &str, method_name: &str, num_args: usize, return_ty: &syn::Ty)
+                         -> (quote::Tokens, quote::Tokens, quote::Tokens) {
+        let serialized_args = (0..num_args).map(|idx| {
+            let mut arg = quote::Tokens::new();
+            arg.append(format!("::serde_json::to_value(&curried_args.{})", idx));
+            quote!{ #arg.unwrap_or_else(|err| panic!("Unable to serialize argument #{} of {}: {}", #idx, #method_name, err)) }
+        }).collect::<Vec<_>>();
+        let fixture_args = quote!{
+            let fixture_args: Vec<::serde_json::Value> = vec![#(#serialized_args),*];
+        };
+
+        match self.requested_mock.fixture {
+            Some(Fixture::Record(..)) => {
+                let record_value = quote!{
+                    let value: #return_ty = value;
+                    let returns = ::serde_json::to_value(&value)
+                                      .unwrap_or_else(|err| panic!("Unable to serialize the return value of {}: {}", #method_name, err));
+                    self.record_fixture_call(#trait_name, #method_name, fixture_args, returns);
+                };
+                (fixture_args, record_value, quote::Tokens::new())
+            },
+            Some(Fixture::Replay(..)) => {
+                let replay = quote!{
+                    if let Some(returns) = self.replay_fixture_call(#trait_name, #method_name, &fixture_args) {
+                        let value: #return_ty = ::serde_json::from_value(returns)
+                            .unwrap_or_else(|err| panic!("Unable to deserialize the replayed return value of {}: {}", #method_name, err));
+                        return value;
+                    }
+                };
+                (fixture_args, quote::Tokens::new(), replay)
+            },
+            None => (quote::Tokens::new(), quote::Tokens::new(), quote::Tokens::new())
+        }
+    }
+
+    /// Generates the forwarding of a call to the spied object, see `#[spy_on(Type)]`.
+    ///
+    /// Returns `None` if the mock has no spied type, the method is static, or it returns a future.
+    fn implement_spy_call(&self, func_name: &syn::Ident, signature: &syn::MethodSig, num_args: usize,
+                          return_kind: &ReturnKind, record_value: &quote::Tokens) -> Option<quote::Tokens> {
+        let spied_type = self.requested_mock.spied_type.as_ref()?;
+        if let &ReturnKind::Future(..) = return_kind {
+            return None;
+        }
+        let receiver = signature.decl.inputs.iter().find(|arg| is_receiver(arg))?;
+        let spied_object = match *receiver {
+            syn::FnArg::SelfRef(_, syn::Mutability::Immutable) => quote!{
+                let spied_object = self.spied_object.borrow();
+                let spied_object = spied_object.as_ref()
+            },
+            syn::FnArg::SelfRef(_, syn::Mutability::Mutable) => quote!{
+                let mut spied_object = self.spied_object.borrow_mut();
+                let spied_object = spied_object.as_mut()
+            },
+            syn::FnArg::SelfValue(..) => quote!{
+                let spied_object = self.spied_object.borrow_mut().take()
+            },
+            _ => return None
+        };
+
+        let lifetimes = self.instantiated_trait.info.generics.lifetimes.iter().map(|def| def.lifetime.clone()).collect();
+        let mut trait_ty = self.instantiated_trait.trait_ty.clone();
+        TraitImplementer::extract_associated_types(&mut trait_ty, lifetimes);
+        let spied_args = (0..num_args).map(|idx| {
+            let mut arg = quote::Tokens::new();
+            arg.append(format!("curried_args.{}", idx));
+            arg
+        }).collect::<Vec<_>>();
+        let spied_value = return_kind.convert(quote!{ <#spied_type as #trait_ty>::#func_name(spied_object, #(#spied_args),*) });
+
+        let value = syn::Ident::from("value");
+        let return_value_ty = return_kind.value_ty();
+        let return_value = return_kind.wrap(&value);
+        Some(quote!{
+            #spied_object.expect("No object to spy on, see `spy_on`");
+            let value: #return_value_ty = #spied_value;
+            #record_value
+            return #return_value;
+        })
+    }
+
     fn generate_argument_names(&self, func_inputs: &[syn::FnArg]) -> Vec<syn::Ident> {
         let mut arg_names = Vec::new();
         let mut arg_idx = 1;
-        for arg in func_inputs {
+        for arg in func_inputs.iter().filter(|arg| !is_receiver(arg)) {
             match arg {
                 &syn::FnArg::Captured(..) | &syn::FnArg::Ignored(..) => {
                     arg_names.push(syn::Ident::from(format!("arg{}", arg_idx)));
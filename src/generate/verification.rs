@@ -0,0 +1,82 @@
+/* Copyright 2017 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use quote;
+
+/// Generates the types of the structured result returned by `verification_report()`.
+pub fn implement_verification_report() -> Vec<quote::Tokens> {
+    let status_item = quote! {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub enum ExpectationStatus {
+            Satisfied,
+            TooFewMatches,
+            TooManyMatches,
+        }
+    };
+
+    let result_item = quote! {
+        #[derive(Clone, Debug, PartialEq, Eq)]
+        pub struct ExpectationResult {
+            pub mock: &'static str,
+            pub requested_trait: &'static str,
+            pub method: &'static str,
+            pub stmt_id: usize,
+            pub statement: String,
+            pub expected_min_matches: Option<usize>,
+            pub expected_max_matches: Option<usize>,
+            pub num_matches: usize,
+            pub status: ExpectationStatus,
+        }
+    };
+
+    let result_impl = quote! {
+        #[allow(dead_code)]
+        impl ExpectationResult {
+            pub fn is_satisfied(&self) -> bool {
+                self.status == ExpectationStatus::Satisfied
+            }
+        }
+    };
+
+    let report_item = quote! {
+        #[derive(Clone, Debug, PartialEq, Eq)]
+        pub struct VerificationReport {
+            pub expectations: Vec<ExpectationResult>,
+        }
+    };
+
+    let report_impl = quote! {
+        #[allow(dead_code)]
+        impl VerificationReport {
+            pub fn is_satisfied(&self) -> bool {
+                self.expectations.iter().all(|expectation| expectation.is_satisfied())
+            }
+
+            pub fn unsatisfied(&self) -> Vec<&ExpectationResult> {
+                self.expectations.iter().filter(|expectation| !expectation.is_satisfied()).collect()
+            }
+        }
+
+        impl std::fmt::Display for VerificationReport {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                for expectation in self.expectations.iter() {
+                    writeln!(f, "{:?}: {} (matched {} times)", expectation.status, expectation.statement, expectation.num_matches)?;
+                }
+                Ok(())
+            }
+        }
+    };
+
+    vec![status_item, result_item, result_impl, report_item, report_impl]
+}
@@ -14,21 +14,47 @@
  */
 use syn;
 use syn::parse::*;
+use quote;
 
 use data::*;
+use expect::{parse_expect_repeat, implement_add_expect_behaviour, implement_create_expect_behaviour};
 use generate::binding_implementer::implement_initialize_binding;
+use generate::{implement_create_behaviour_guard, implement_statement_fn, can_capture};
 
 
 named!(pub parse_bind -> BindingField,
     do_parse!(
-        punct!("bind") >> name: call!(syn::parse::ident) >>
-        punct!(":") >> ty: call!(syn::parse::ty) >>
+        punct!("bind") >> is_mut: option!(keyword!("mut")) >> name: call!(syn::parse::ident) >>
+        ty: option!(preceded!(punct!(":"), syn::parse::ty)) >>
         punct!("=") >> initializer: call!(syn::parse::expr) >>
-        (BindingField { name, ty, initializer })
+        (BindingField { is_mut: is_mut.is_some(), name, ty, initializer })
     )
 );
 
-named!(parse_given_func -> (syn::Ident, BehaviourMatcher, Return, GivenRepeat),
+named!(parse_given_repeat -> GivenRepeat,
+    alt!( preceded!(keyword!("times"), syn::parse::expr) => { GivenRepeat::Times }
+        | keyword!("always") => { |_| GivenRepeat::Always }
+    )
+);
+
+named!(parse_fault_schedule -> FaultSchedule,
+    alt!( preceded!(keyword!("then_fail_after"), syn::parse::expr) => { FaultSchedule::After }
+        | preceded!(keyword!("then_fail_on"), syn::parse::expr) => { FaultSchedule::OnCalls }
+        | do_parse!(
+              keyword!("then_fail_randomly") >> fraction: call!(syn::parse::expr) >>
+              keyword!("seed") >> seed: call!(syn::parse::expr) >>
+              (FaultSchedule::Randomly(fraction, seed))
+          )
+    )
+);
+
+named!(parse_delay -> Delay,
+    alt!( preceded!(keyword!("then_sleep"), syn::parse::expr) => { Delay::Sleep }
+        | preceded!(keyword!("then_block_until"), syn::parse::expr) => { Delay::BlockUntil }
+    )
+);
+
+named!(parse_given_func -> (syn::Ident, BehaviourMatcher, Return, GivenRepeat, Option<ExpectRepeat>, Option<syn::Expr>),
     do_parse!(
         method: call!(syn::parse::ident) >>
         args: alt!(
@@ -37,19 +63,41 @@ named!(parse_given_func -> (syn::Ident, BehaviourMatcher, Return, GivenRepeat),
                     if es.is_empty() { BehaviourMatcher::Void }
                     else { BehaviourMatcher::PerArgument(es) }
               }
-            | call!(syn::parse::expr) => { |e| BehaviourMatcher::Explicit(e) }
+            | call!(syn::parse::expr) => { BehaviourMatcher::Explicit }
         ) >>
-        return_stmt: alt!( preceded!(keyword!("then_return"), syn::parse::expr) => { |e| Return::FromValue(e) }
-                         | preceded!(keyword!("then_return_from"), syn::parse::expr) => { |e| Return::FromCall(e) }
-                         | preceded!(keyword!("then_return_ref"), syn::parse::expr) => { |e| Return::FromValue(e) }
-                         | preceded!(keyword!("then_return_ref_from"), syn::parse::expr) => { |e| Return::FromCall(e) }
+        return_stmt: alt!( preceded!(keyword!("then_return"), syn::parse::expr) => { Return::FromValue }
+                         | preceded!(keyword!("then_return_from"), syn::parse::expr) => { Return::FromCall }
+                         | preceded!(keyword!("then_return_iter"), syn::parse::expr) => { Return::FromIter }
+                         | preceded!(keyword!("then_return_ref"), syn::parse::expr) => { Return::FromValue }
+                         | preceded!(keyword!("then_return_ref_from"), syn::parse::expr) => { Return::FromCall }
                          | keyword!("then_spy_on_object") => { |_| Return::FromSpy }
+                         | preceded!(keyword!("then_fail_with"), syn::parse::expr) => { Return::FailWith }
+                         | preceded!(keyword!("then_panic_with"), syn::parse::expr) => { Return::PanicWith }
+                         | preceded!(keyword!("then_panic_with_payload"), syn::parse::expr) => { Return::PanicWithPayload }
                          | keyword!("then_panic") => { |_| Return::Panic }
+                         | call!(parse_delay) => { |delay| Return::Delayed(None, delay) }
         ) >>
-        repeat: alt!( preceded!(keyword!("times"), syn::parse::expr) => { |e| GivenRepeat::Times(e) }
-                    | keyword!("always") => { |_| GivenRepeat::Always }
+        trigger: option!(preceded!(keyword!("after_trigger"), syn::parse::expr)) >>
+        delay: option!(parse_delay) >>
+        faults: option!(tuple!(parse_fault_schedule, preceded!(keyword!("with"), syn::parse::expr))) >>
+        repeats: alt!( tuple!(parse_given_repeat, option!(preceded!(keyword!("expect"), parse_expect_repeat)))
+                     | preceded!(keyword!("expect"), parse_expect_repeat) => { |e| (GivenRepeat::Always, Some(e)) }
         ) >>
-        (method, args, return_stmt, repeat)
+        priority: option!(preceded!(keyword!("with_priority"), syn::parse::expr)) >>
+        (method, args, {
+            let return_stmt = match trigger {
+                Some(trigger) => Return::AfterTrigger(Box::new(return_stmt), trigger),
+                None => return_stmt
+            };
+            let return_stmt = match faults {
+                Some((schedule, error)) => Return::InjectFaults(Box::new(return_stmt), schedule, error),
+                None => return_stmt
+            };
+            match delay {
+                Some(delay) => Return::Delayed(Some(Box::new(return_stmt)), delay),
+                None => return_stmt
+            }
+        }, repeats.0, repeats.1, priority)
     )
 );
 
@@ -66,7 +114,10 @@ named!(pub parse_given -> Vec<GivenStatement>,
             method: func.0,
             matcher: func.1,
             return_stmt: func.2,
-            repeat: func.3
+            repeat: func.3,
+            expect_repeat: func.4,
+            priority: func.5,
+            captures: false
         }])
     )
 );
@@ -85,21 +136,35 @@ named!(pub parse_given_trait_block -> Vec<GivenStatement>,
                 method: func.0,
                 matcher: func.1,
                 return_stmt: func.2,
-                repeat: func.3
+                repeat: func.3,
+                expect_repeat: func.4,
+                priority: func.5,
+                captures: false
             })
         )) >> punct!("}") >>
         (statements)
     )
 );
 
+named!(given_block_content -> (Vec<BindingField>, Vec<GivenStatement>),
+    tuple!(
+        terminated_list!(punct!(";"), parse_bind),
+        map!(terminated_list!(punct!(";"), alt!(parse_given | parse_given_trait_block)),
+             |statements_list: Vec<Vec<GivenStatement>>| statements_list.into_iter().flat_map(|stmts| stmts.into_iter()).collect::<Vec<_>>()
+        )
+    )
+);
+
 named!(pub parse_givens -> (Vec<BindingField>, Vec<GivenStatement>),
     delimited!(tuple!(keyword!("given"), punct!("!"), punct!("{")),
-               tuple!(
-                   terminated_list!(punct!(";"), parse_bind),
-                   map!(terminated_list!(punct!(";"), alt!(parse_given | parse_given_trait_block)),
-                        |statements_list: Vec<Vec<GivenStatement>>| statements_list.into_iter().flat_map(|stmts| stmts.into_iter()).collect::<Vec<_>>()
-                   )
-               ),
+               given_block_content,
+               punct!("}")
+    )
+);
+
+named!(pub parse_scoped_givens -> (Vec<BindingField>, Vec<GivenStatement>),
+    delimited!(tuple!(keyword!("given_scoped"), punct!("!"), punct!("{")),
+               given_block_content,
                punct!("}")
     )
 );
@@ -107,45 +172,155 @@ named!(pub parse_givens -> (Vec<BindingField>, Vec<GivenStatement>),
 
 pub fn handle_given(source: &str, absolute_position: usize) -> (String, String) {
     if let IResult::Done(remainder, (binding_fields, given_definitions)) = parse_givens(source) {
-        let mut statements = acquire!(GIVEN_STATEMENTS);
-
-        let mut add_statements = Vec::new();
-        for (idx, mut stmt) in given_definitions.into_iter().enumerate() {
-            stmt.block_id = absolute_position;
-            stmt.stmt_id = absolute_position + idx;
-            let stmt_id = stmt.stmt_id;
-
-            {
-                let mock_var = &stmt.mock_var;
-                let ufc_trait_name = stmt.trait_name();
-                let method_name = stmt.method_name();
-
-                let stmt_repr = format!("{}", stmt);
-                add_statements.push(match &stmt.repeat {
-                    &GivenRepeat::Always => quote!( #mock_var.add_given_behaviour(#ufc_trait_name, #method_name, mock::GivenBehaviour::with(#stmt_id, binding.clone(), #stmt_repr)); ),
-                    &GivenRepeat::Times(ref expr) => quote!( #mock_var.add_given_behaviour(#ufc_trait_name, #method_name, mock::GivenBehaviour::with_times(#expr, #stmt_id, binding.clone(), #stmt_repr)); ),
-                });
+        let (given_block, _, _) = implement_given_block(binding_fields, given_definitions, absolute_position);
+        (given_block.to_string(), remainder.to_owned())
+    } else { panic!("Expecting a `given!` definition: <MOCK_VAR_NAME as MOCKED_TRAIT>::METHOD(MATCHER, ...) THEN REPEAT; ..."); }
+}
+
+/// Handles a `given_scoped!` block which evaluates to a guard removing the block's behaviours once dropped.
+pub fn handle_given_scoped(source: &str, absolute_position: usize) -> (String, String) {
+    if let IResult::Done(remainder, (binding_fields, given_definitions)) = parse_scoped_givens(source) {
+        let (given_block, given_ids_per_mock, expect_ids_per_mock) = implement_given_block(binding_fields, given_definitions, absolute_position);
+
+        let mut drop_actions = given_ids_per_mock.into_iter().map(|(mock_var, stmt_ids)| quote! {
+            #mock_var.remove_given_behaviours(&[#(#stmt_ids),*]);
+        }).collect::<Vec<_>>();
+        drop_actions.extend(expect_ids_per_mock.into_iter().map(|(mock_var, stmt_ids)| {
+            let stmt_ids = quote!(&[#(#stmt_ids),*]);
+            quote! {
+                #mock_var.verify_expect_behaviours(#stmt_ids);
+                #mock_var.remove_expect_behaviours(#stmt_ids);
             }
+        }));
+        let guard = implement_create_behaviour_guard(&drop_actions);
+
+        let scoped_given_block = quote! {{
+            #given_block
+            #guard
+        }};
+        (scoped_given_block.to_string(), remainder.to_owned())
+    } else { panic!("Expecting a `given_scoped!` definition: <MOCK_VAR_NAME as MOCKED_TRAIT>::METHOD(MATCHER, ...) THEN REPEAT; ..."); }
+}
 
-            statements.entry(stmt.ufc_trait.clone())
-                      .or_insert_with(|| Vec::new())
-                      .push(stmt);
+/// Generates the statements adding the behaviours of a given block to the mocks.
+///
+/// The expectations of combined given-and-expect statements are added as well.
+/// Returns the generated statements and the ids of the added given and expect statements grouped by mock variable.
+fn implement_given_block(binding_fields: Vec<BindingField>, given_definitions: Vec<GivenStatement>, absolute_position: usize)
+                         -> (quote::Tokens, StmtIdsPerMock, StmtIdsPerMock) {
+    let mut statements = acquire!(GIVEN_STATEMENTS);
+    let mut expect_statements = acquire!(EXPECT_STATEMENTS);
+
+    let binding = Binding {
+        block_id: absolute_position,
+        fields: binding_fields
+    };
+
+    let mut add_statements = Vec::new();
+    let mut given_ids_per_mock = Vec::new();
+    let mut expect_ids_per_mock = Vec::new();
+    for (idx, mut stmt) in given_definitions.into_iter().enumerate() {
+        stmt.block_id = absolute_position;
+        stmt.stmt_id = absolute_position + idx;
+        stmt.captures = can_capture(&stmt.ufc_trait, &stmt.method, &stmt.matcher, Some(&stmt.return_stmt));
+        if binding.has_inferred_types() && !stmt.captures {
+            panic!("Values bound without a type can only be used by statements capturing their environment but `{}` does not. \
+                    State the types of the bound values: bind VARIABLE: TYPE = EXPRESSION;", stmt);
+        }
+        let stmt_id = stmt.stmt_id;
+        let expect_stmt = stmt.expect_statement();
+
+        {
+            let mock_var = &stmt.mock_var;
+            let ufc_trait_name = stmt.trait_name();
+            let method_name = stmt.method_name();
+
+            let stmt_repr = format!("{}", stmt);
+            let behaviour = match stmt.repeat {
+                GivenRepeat::Always => quote!( mock::GivenBehaviour::with(#stmt_id, binding.clone(), #stmt_repr) ),
+                GivenRepeat::Times(ref expr) => quote!( mock::GivenBehaviour::with_times(#expr, #stmt_id, binding.clone(), #stmt_repr) ),
+            };
+            let behaviour = match stmt.return_stmt.fault_schedule() {
+                Some(schedule) => {
+                    let faults = match *schedule {
+                        FaultSchedule::After(ref num_calls) => quote!( mock::FaultInjection::after(#num_calls) ),
+                        FaultSchedule::OnCalls(ref calls) => quote!( mock::FaultInjection::on_calls(#calls) ),
+                        FaultSchedule::Randomly(ref fraction, ref seed) => quote!( mock::FaultInjection::randomly(#fraction, #seed) )
+                    };
+                    quote!( #behaviour.with_faults(#faults) )
+                },
+                None => behaviour
+            };
+            let behaviour = match stmt.priority {
+                Some(ref priority) => quote!( #behaviour.with_priority(#priority) ),
+                None => behaviour
+            };
+            add_statements.push(if stmt.captures {
+                implement_add_captured_given_behaviour(&stmt, behaviour, expect_stmt.as_ref())
+            } else {
+                let add_expect_behaviour = expect_stmt.as_ref().map(implement_add_expect_behaviour);
+                quote! {
+                    #mock_var.add_given_behaviour(#ufc_trait_name, #method_name, #behaviour);
+                    #add_expect_behaviour
+                }
+            });
+            add_stmt_id_for_mock(&mut given_ids_per_mock, mock_var, stmt_id);
         }
 
-        let binding = Binding {
-            block_id: absolute_position,
-            fields: binding_fields
-        };
-        let binding_initialization = implement_initialize_binding(&binding);
-        acquire!(BINDINGS).push(binding);
+        if let Some(expect_stmt) = expect_stmt {
+            add_stmt_id_for_mock(&mut expect_ids_per_mock, &stmt.mock_var, stmt_id);
+            expect_statements.entry(expect_stmt.ufc_trait.clone())
+                             .or_default()
+                             .push(expect_stmt);
+        }
 
-        let given_block = quote! {
-            let binding = std::rc::Rc::new(#binding_initialization);
-            #(#add_statements)*
-        };
+        statements.entry(stmt.ufc_trait.clone())
+                  .or_default()
+                  .push(stmt);
+    }
 
-        return (given_block.to_string(), remainder.to_owned());
-    } else { panic!("Expecting a `given!` definition: <MOCK_VAR_NAME as MOCKED_TRAIT>::METHOD(MATCHER, ...) THEN REPEAT; ..."); }
+    let binding_initialization = implement_initialize_binding(&binding);
+    acquire!(BINDINGS).push(binding);
+
+    let given_block = quote! {
+        let binding = std::rc::Rc::new(#binding_initialization);
+        #(#add_statements)*
+    };
+
+    (given_block, given_ids_per_mock, expect_ids_per_mock)
+}
+
+/// Generates the statements adding a given behaviour which is evaluated by a closure capturing the block's environment.
+///
+/// The expectation of a combined given-and-expect statement shares the closure.
+fn implement_add_captured_given_behaviour(stmt: &GivenStatement, behaviour: quote::Tokens, expect_stmt: Option<&ExpectStatement>) -> quote::Tokens {
+    let mock_var = &stmt.mock_var;
+    let ufc_trait_name = stmt.trait_name();
+    let method_name = stmt.method_name();
+    let capture_fn = syn::Ident::from(format!("capture_statement_{}", stmt.stmt_id));
+    let statement_fn = implement_statement_fn(&stmt.matcher, Some(&stmt.return_stmt));
+
+    match expect_stmt {
+        None => quote! {{
+            let statement_fn = #mock_var.#capture_fn(#statement_fn);
+            #mock_var.add_given_behaviour(#ufc_trait_name, #method_name, #behaviour.capturing(statement_fn));
+        }},
+        Some(expect_stmt) => {
+            let expect_behaviour = implement_create_expect_behaviour(expect_stmt);
+            quote! {{
+                let statement_fn = #mock_var.#capture_fn(#statement_fn);
+                #mock_var.add_given_behaviour(#ufc_trait_name, #method_name, #behaviour.capturing(statement_fn.clone()));
+                #mock_var.add_expect_behaviour(#ufc_trait_name, #method_name, #expect_behaviour.capturing(statement_fn));
+            }}
+        }
+    }
+}
+
+fn add_stmt_id_for_mock(stmt_ids_per_mock: &mut StmtIdsPerMock, mock_var: &syn::Ident, stmt_id: usize) {
+    match stmt_ids_per_mock.iter_mut().find(|&&mut (ref var, _)| var == mock_var) {
+        Some(&mut (_, ref mut stmt_ids)) => stmt_ids.push(stmt_id),
+        None => stmt_ids_per_mock.push((mock_var.clone(), vec![stmt_id]))
+    }
 }
 
 
@@ -162,11 +337,30 @@ mod test {
         fn should_parse_bind() {
             let field = parse_bind("bind x: i32 = 1 + 2").expect("");
 
+            assert_that!(&field.is_mut, eq(false));
             assert_that!(&field.name, eq(syn::Ident::from("x")));
-            assert_that!(&field.ty, is_variant!(syn::Ty::Path));
+            assert_that!(field.ty.as_ref().expect("a type is expected"), is_variant!(syn::Ty::Path));
             assert_that!(&field.initializer.node, is_variant!(syn::ExprKind::Binary));
         }
 
+        #[test]
+        fn should_parse_mutable_bind() {
+            let field = parse_bind("bind mut x: Vec<i32> = Vec::new()").expect("");
+
+            assert_that!(&field.is_mut, eq(true));
+            assert_that!(&field.name, eq(syn::Ident::from("x")));
+            assert_that!(field.ty.as_ref().expect("a type is expected"), is_variant!(syn::Ty::Path));
+        }
+
+        #[test]
+        fn should_parse_bind_without_type() {
+            let field = parse_bind("bind x = vec![1, 2].into_iter()").expect("");
+
+            assert_that!(&field.name, eq(syn::Ident::from("x")));
+            assert_that!(&field.ty, eq(None));
+            assert_that!(&field.initializer.node, is_variant!(syn::ExprKind::MethodCall));
+        }
+
         #[test]
         fn should_parse_given_return_from_call() {
             let stmt = &parse_given("<mock as MyTrait>::foo() then_return_from || { 2 } always").expect("")[0];
@@ -190,6 +384,16 @@ mod test {
             assert_that!(&stmt.repeat, eq(GivenRepeat::Always));
         }
 
+        #[test]
+        fn should_parse_given_return_iter() {
+            let stmt = &parse_given("<mock as MyTrait>::foo() then_return_iter vec![1, 2] always").expect("")[0];
+
+            assert_that!(&stmt.mock_var, eq(syn::Ident::from("mock")));
+            assert_that!(&stmt.method, eq(syn::Ident::from("foo")));
+            assert_that!(&stmt.return_stmt, eq(Return::FromIter(syn::parse::expr("vec![1, 2]").expect(""))));
+            assert_that!(&stmt.repeat, eq(GivenRepeat::Always));
+        }
+
         #[test]
         fn should_parse_given_spy_on_object() {
             let stmt = &parse_given("<mock as MyTrait>::foo() then_spy_on_object always").expect("")[0];
@@ -212,6 +416,94 @@ mod test {
             assert_that!(&stmt.repeat, eq(GivenRepeat::Always));
         }
 
+        #[test]
+        fn should_parse_given_fail_with() {
+            let stmt = &parse_given("<mock as MyTrait>::foo() then_fail_with MyError always").expect("")[0];
+
+            assert_that!(&stmt.return_stmt, eq(Return::FailWith(syn::parse::expr("MyError").expect(""))));
+        }
+
+        #[test]
+        fn should_parse_given_fault_injection() {
+            let stmt = &parse_given("<mock as MyTrait>::foo() then_return Ok(1) then_fail_randomly 0.5 seed 42 with MyError always").expect("")[0];
+
+            assert_that!(&stmt.return_stmt, eq(Return::InjectFaults(
+                Box::new(Return::FromValue(syn::parse::expr("Ok(1)").expect(""))),
+                FaultSchedule::Randomly(syn::parse::expr("0.5").expect(""), syn::parse::expr("42").expect("")),
+                syn::parse::expr("MyError").expect("")
+            )));
+            assert_that!(&stmt.repeat, eq(GivenRepeat::Always));
+        }
+
+        #[test]
+        fn should_parse_given_delay() {
+            let stmt = &parse_given("<mock as MyTrait>::foo() then_return 1 then_sleep duration always").expect("")[0];
+
+            assert_that!(&stmt.return_stmt, eq(Return::Delayed(
+                Some(Box::new(Return::FromValue(syn::parse::expr("1").expect("")))),
+                Delay::Sleep(syn::parse::expr("duration").expect(""))
+            )));
+        }
+
+        #[test]
+        fn should_parse_given_block_without_value() {
+            let stmt = &parse_given("<mock as MyTrait>::foo() then_block_until barrier always").expect("")[0];
+
+            assert_that!(&stmt.return_stmt, eq(Return::Delayed(None, Delay::BlockUntil(syn::parse::expr("barrier").expect("")))));
+        }
+
+        #[test]
+        fn should_parse_given_with_priority() {
+            let stmt = &parse_given("<mock as MyTrait>::foo() then_return 1 times 2 with_priority 10").expect("")[0];
+
+            assert_that!(&stmt.repeat, eq(GivenRepeat::Times(syn::parse::expr("2").expect(""))));
+            assert_that!(&stmt.priority, eq(Some(syn::parse::expr("10").expect(""))));
+        }
+
+        #[test]
+        fn should_parse_given_panic_with() {
+            let stmt = &parse_given("<mock as MyTrait>::foo() then_panic_with \"message\" always").expect("")[0];
+            assert_that!(&stmt.return_stmt, eq(Return::PanicWith(syn::parse::expr("\"message\"").expect(""))));
+
+            let stmt = &parse_given("<mock as MyTrait>::foo() then_panic_with_payload 42 always").expect("")[0];
+            assert_that!(&stmt.return_stmt, eq(Return::PanicWithPayload(syn::parse::expr("42").expect(""))));
+        }
+
+        #[test]
+        fn should_parse_given_after_trigger() {
+            let stmt = &parse_given("<mock as MyTrait>::foo() then_return 1 after_trigger bound.trigger always").expect("")[0];
+
+            assert_that!(&stmt.mock_var, eq(syn::Ident::from("mock")));
+            assert_that!(&stmt.method, eq(syn::Ident::from("foo")));
+            assert_that!(&stmt.return_stmt, eq(Return::AfterTrigger(Box::new(Return::FromValue(syn::parse::expr("1").expect(""))),
+                                                                    syn::parse::expr("bound.trigger").expect(""))));
+            assert_that!(&stmt.repeat, eq(GivenRepeat::Always));
+        }
+
+        #[test]
+        fn should_parse_given_with_expect() {
+            let stmt = &parse_given("<mock as MyTrait>::foo() then_return 1 expect times 2").expect("")[0];
+
+            assert_that!(&stmt.return_stmt, eq(Return::FromValue(syn::parse::expr("1").expect(""))));
+            assert_that!(&stmt.repeat, eq(GivenRepeat::Always));
+            assert_that!(&stmt.expect_repeat, eq(Some(ExpectRepeat::Times(syn::parse::expr("2").expect("")))));
+        }
+
+        #[test]
+        fn should_parse_given_times_with_expect() {
+            let stmt = &parse_given("<mock as MyTrait>::foo() then_return 1 times 3 expect at_least 1").expect("")[0];
+
+            assert_that!(&stmt.repeat, eq(GivenRepeat::Times(syn::parse::expr("3").expect(""))));
+            assert_that!(&stmt.expect_repeat, eq(Some(ExpectRepeat::AtLeast(syn::parse::expr("1").expect("")))));
+        }
+
+        #[test]
+        fn should_render_given_with_expect_and_priority() {
+            let stmt = &parse_given("<mock as MyTrait>::foo(1) then_return 2 times 3 expect between 1, 2 with_priority 10").expect("")[0];
+
+            assert_that!(&format!("{}", stmt), eq(String::from("MyTrait::foo(1) then_return 2 times 3 expect between 1, 2 with_priority 10")));
+        }
+
         #[test]
         fn should_parse_given_times() {
             let stmt = &parse_given("<mock as MyTrait>::foo() then_return 1 times 2").expect("")[0];
@@ -245,6 +537,16 @@ mod test {
             assert_that!(&stmt.repeat, eq(GivenRepeat::Always));
         }
 
+        #[test]
+        fn should_parse_given_pattern_args() {
+            let stmt = &parse_given("<mock as MyTrait>::foo(pat!(Some(x) if x > 2), pat!(_)) then_return_from |_| x always").expect("")[0];
+
+            assert_that!(&stmt.method, eq(syn::Ident::from("foo")));
+            assert_that!(&stmt.matcher, eq(BehaviourMatcher::PerArgument(vec![syn::parse::expr("pat!(Some(x) if x > 2)").expect(""),
+                                                                              syn::parse::expr("pat!(_)").expect("")])));
+            assert_that!(&stmt.repeat, eq(GivenRepeat::Always));
+        }
+
         #[test]
         fn should_parse_given_matcher() {
             let stmt = &parse_given("<mock as MyTrait>::foo |a,b| true then_return 1 always").expect("")[0];
@@ -272,6 +574,14 @@ mod test {
             assert_that!(&givens.len(), eq(3));
         }
 
+        #[test]
+        fn should_parse_scoped_givens() {
+            let (binds, givens) = parse_scoped_givens("given_scoped! { bind x: i32 = 1; <mock as MyTrait>::foo() then_return 1 always; }").expect("");
+
+            assert_that!(&binds.len(), eq(1));
+            assert_that!(&givens.len(), eq(1));
+        }
+
         #[test]
         fn should_parse_givens_with_bind() {
             let (binds, givens) = parse_givens("given! { bind x: i32 = 1; bind x: f32 = 2.0; <mock as MyTrait>::foo() then_return 1 always; }").expect("");
@@ -281,4 +591,24 @@ mod test {
             assert_that!(&givens.len(), eq(1));
         }
     }
+
+    mod capture {
+        use super::*;
+        use super::super::*;
+
+        #[test]
+        #[should_panic(expected = "uses the variables `limit`, `name` of the surrounding scope")]
+        fn should_panic_if_statement_for_unknown_trait_uses_variables() {
+            let stmt = &parse_given("<mock as UnknownTrait>::foo(lt(limit)) then_return name.len() always").expect("")[0];
+
+            can_capture(&stmt.ufc_trait, &stmt.method, &stmt.matcher, Some(&stmt.return_stmt));
+        }
+
+        #[test]
+        fn should_not_capture_for_unknown_trait() {
+            let stmt = &parse_given("<mock as UnknownTrait>::foo |&(x,)| { let y = x; y < bound.limit } then_return_from |&(x,)| eq(x) always").expect("")[0];
+
+            assert_that!(&can_capture(&stmt.ufc_trait, &stmt.method, &stmt.matcher, Some(&stmt.return_stmt)), eq(false));
+        }
+    }
 }
@@ -13,10 +13,11 @@
  * limitations under the License.
  */
 #![feature(proc_macro)]
-#![recursion_limit = "128"]
+#![recursion_limit = "256"]
 
 #[macro_use] mod acquire;
 mod new_mock;
+mod mock_dispatch;
 mod given;
 mod expect;
 mod generate;
@@ -32,11 +33,12 @@ extern crate syn;
 #[cfg(test)]#[macro_use]
 extern crate galvanic_assert;
 
-use proc_macro::TokenStream;
+use proc_macro::{Group, Punct, Spacing, Span, TokenStream, TokenTree};
 
 use new_mock::handle_new_mock;
-use given::handle_given;
-use expect::handle_expect_interactions;
+use mock_dispatch::{handle_mockable_fn, handle_mockable_impl};
+use given::{handle_given, handle_given_scoped};
+use expect::{handle_expect_interactions, handle_expect_interactions_scoped};
 use generate::handle_generate_mocks;
 use data::*;
 
@@ -67,8 +69,8 @@ named!(parse_trait_path -> MockedTraitLocation,
 
 #[proc_macro_attribute]
 pub fn mockable(args: TokenStream, input: TokenStream) -> TokenStream {
-    let s = input.to_string();
-    let trait_item = syn::parse_item(&s).expect("Expecting a trait definition.");
+    let (stripped, async_methods) = strip_async_methods(mark_trait_objects(input.clone()));
+    let trait_item = syn::parse_item(&stripped.to_string()).expect("Expecting a trait definition, a function definition, or an impl block.");
 
     let args_str = &args.to_string();
 
@@ -77,7 +79,7 @@ pub fn mockable(args: TokenStream, input: TokenStream) -> TokenStream {
             let mut mockable_traits = acquire!(MOCKABLE_TRAITS);
 
             if args_str.is_empty() {
-                mockable_traits.insert(trait_item.ident.clone().into(), TraitInfo::new(safety, generics, bounds, items));
+                mockable_traits.insert(trait_item.ident.clone().into(), TraitInfo::new(safety, generics, bounds, items, async_methods));
                 return input;
             }
 
@@ -87,20 +89,150 @@ pub fn mockable(args: TokenStream, input: TokenStream) -> TokenStream {
             match trait_location {
                 MockedTraitLocation::TraitDef(mut trait_path) => {
                     trait_path.segments.push(trait_item.ident.clone().into());
-                    mockable_traits.insert(trait_path, TraitInfo::new(safety, generics, bounds, items));
+                    mockable_traits.insert(trait_path, TraitInfo::new(safety, generics, bounds, items, async_methods));
                     input
                 },
                 MockedTraitLocation::Referred(mut trait_path) => {
                     trait_path.segments.push(trait_item.ident.clone().into());
-                    mockable_traits.insert(trait_path, TraitInfo::new(safety, generics, bounds, items));
+                    mockable_traits.insert(trait_path, TraitInfo::new(safety, generics, bounds, items, async_methods));
                     "".parse().unwrap()
                 }
             }
         },
-        _ => panic!("Expecting a trait definition.")
+        syn::ItemKind::Fn(..) => unmark_trait_objects(handle_mockable_fn(&trait_item, parse_location_of_mockable_trait(args_str))
+                                                          .to_string().parse().unwrap()),
+        syn::ItemKind::Impl(..) => unmark_trait_objects(handle_mockable_impl(&trait_item, parse_location_of_mockable_trait(args_str))
+                                                            .to_string().parse().unwrap()),
+        _ => panic!("Expecting a trait definition, a function definition, or an impl block.")
     }
 }
 
+/// Parses the module path of a mockable trait generated for a function or an `impl` block.
+fn parse_location_of_mockable_trait(args_str: &str) -> Option<syn::Path> {
+    if args_str.is_empty() {
+        return None;
+    }
+
+    match parse_trait_path(args_str).expect("#[mockable(..)] requires the absolute path of the function's/impl block's module.") {
+        MockedTraitLocation::TraitDef(path) => Some(path),
+        MockedTraitLocation::Referred(..) => panic!("`extern`/`intern` are not supported by #[mockable] for functions or impl blocks.")
+    }
+}
+
+/// Removes the `async` qualifier from the methods of a trait definition.
+///
+/// The parser does not know about `async fn` so the qualifier is removed before parsing.
+/// Returns the trait definition without the qualifiers and the names of all `async` methods.
+fn strip_async_methods(tokens: TokenStream) -> (TokenStream, Vec<syn::Ident>) {
+    let mut async_methods = Vec::new();
+    let stripped = strip_async_methods_into(tokens, &mut async_methods);
+    (stripped, async_methods)
+}
+
+fn strip_async_methods_into(tokens: TokenStream, async_methods: &mut Vec<syn::Ident>) -> TokenStream {
+    let tokens = tokens.into_iter().collect::<Vec<_>>();
+    let mut stripped = Vec::new();
+
+    for (idx, token) in tokens.iter().enumerate() {
+        match *token {
+            TokenTree::Ident(ref ident) if ident.to_string() == "async" && is_ident(tokens.get(idx + 1), "fn") => {
+                if let Some(TokenTree::Ident(method)) = tokens.get(idx + 2) {
+                    async_methods.push(syn::Ident::from(method.to_string()));
+                }
+            },
+            TokenTree::Group(ref group) => {
+                let stripped_stream = strip_async_methods_into(group.stream(), async_methods);
+                stripped.push(TokenTree::Group(with_span(Group::new(group.delimiter(), stripped_stream), group.span())));
+            },
+            _ => stripped.push(token.clone())
+        }
+    }
+
+    stripped.into_iter().collect()
+}
+
+/// Marks the trait objects in `tokens` such that they can be parsed.
+///
+/// The parser does not know about the `dyn` keyword. A trait object `dyn Bound + ..` is therefore
+/// rewritten to `?dyn + Bound + ..` which is parsed as a trait object with the additional bound `?dyn`.
+/// This keeps single bound trait objects, e.g., `dyn Future<Output = T>`, distinguishable from paths.
+/// Once the code has been generated the marker is turned back into `dyn` by `unmark_trait_objects`.
+fn mark_trait_objects(tokens: TokenStream) -> TokenStream {
+    let tokens = tokens.into_iter().collect::<Vec<_>>();
+    let mut marked = Vec::new();
+
+    for (idx, token) in tokens.iter().enumerate() {
+        match *token {
+            TokenTree::Ident(ref ident) if ident.to_string() == "dyn" && starts_trait_bound(tokens.get(idx + 1)) => {
+                marked.push(TokenTree::Punct(Punct::new('?', Spacing::Alone)));
+                marked.push(token.clone());
+                marked.push(TokenTree::Punct(Punct::new('+', Spacing::Alone)));
+            },
+            TokenTree::Group(ref group) => {
+                let marked_stream = mark_trait_objects(group.stream());
+                marked.push(TokenTree::Group(with_span(Group::new(group.delimiter(), marked_stream), group.span())));
+            },
+            _ => marked.push(token.clone())
+        }
+    }
+
+    marked.into_iter().collect()
+}
+
+/// Replaces the markers inserted by `mark_trait_objects` with the `dyn` keyword.
+fn unmark_trait_objects(tokens: TokenStream) -> TokenStream {
+    let tokens = tokens.into_iter().collect::<Vec<_>>();
+    let mut unmarked = Vec::new();
+
+    let mut idx = 0;
+    while idx < tokens.len() {
+        match tokens[idx] {
+            TokenTree::Punct(ref punct) if punct.as_char() == '?' && is_ident(tokens.get(idx + 1), "dyn")
+                                           && is_punct(tokens.get(idx + 2), '+') => {
+                unmarked.push(tokens[idx + 1].clone());
+                idx += 3;
+                continue;
+            },
+            TokenTree::Group(ref group) => {
+                let unmarked_stream = unmark_trait_objects(group.stream());
+                unmarked.push(TokenTree::Group(with_span(Group::new(group.delimiter(), unmarked_stream), group.span())));
+            },
+            ref token => unmarked.push(token.clone())
+        }
+        idx += 1;
+    }
+
+    unmarked.into_iter().collect()
+}
+
+/// Checks if a token following `dyn` starts a trait bound, i.e., if `dyn` is used as keyword and not as identifier.
+fn starts_trait_bound(token: Option<&TokenTree>) -> bool {
+    match token {
+        Some(TokenTree::Ident(ident)) => ident.to_string() != "as" && ident.to_string() != "in",
+        Some(TokenTree::Punct(punct)) => punct.as_char() == '?' || punct.as_char() == '\''
+                                              || (punct.as_char() == ':' && punct.spacing() == Spacing::Joint),
+        _ => false
+    }
+}
+
+fn is_ident(token: Option<&TokenTree>, name: &str) -> bool {
+    match token {
+        Some(TokenTree::Ident(ident)) => ident.to_string() == name,
+        _ => false
+    }
+}
+
+fn is_punct(token: Option<&TokenTree>, c: char) -> bool {
+    match token {
+        Some(TokenTree::Punct(punct)) => punct.as_char() == c,
+        _ => false
+    }
+}
+
+fn with_span(mut group: Group, span: Span) -> Group {
+    group.set_span(span);
+    group
+}
 
 #[proc_macro_attribute]
 pub fn use_mocks(_: TokenStream, input: TokenStream) -> TokenStream {
@@ -108,7 +240,7 @@ pub fn use_mocks(_: TokenStream, input: TokenStream) -> TokenStream {
 
     // to parse the macros related to mock ussage the function is converted to string form
     let mut reassembled = String::new();
-    let parsed = syn::parse_item(&input.to_string()).unwrap();
+    let parsed = syn::parse_item(&mark_trait_objects(input).to_string()).unwrap();
     let mut remainder = quote!(#parsed).to_string();
 
     // parse one macro a time then search for the next macro in the remaining string
@@ -125,6 +257,8 @@ pub fn use_mocks(_: TokenStream, input: TokenStream) -> TokenStream {
                     NewMock(pos) => handle_macro(&remainder, pos, absolute_pos, handle_new_mock),
                     Given(pos) => handle_macro(&remainder, pos, absolute_pos, handle_given),
                     ExpectInteractions(pos) => handle_macro(&remainder, pos, absolute_pos, handle_expect_interactions),
+                    GivenScoped(pos) => handle_macro(&remainder, pos, absolute_pos, handle_given_scoped),
+                    ExpectInteractionsScoped(pos) => handle_macro(&remainder, pos, absolute_pos, handle_expect_interactions_scoped),
                 };
 
                 absolute_pos = new_absolute_pos;
@@ -135,7 +269,8 @@ pub fn use_mocks(_: TokenStream, input: TokenStream) -> TokenStream {
     }
 
     // once all macro invocations have been removed from the string (and replaced with the actual mock code) it can be parsed back into a function item
-    let mut mock_using_item = syn::parse_item(&reassembled).expect("Reassembled function whi");
+    let reassembled = mark_trait_objects(reassembled.parse().expect("galvanic_mock internal error: unable to tokenize reassembled function"));
+    let mut mock_using_item = syn::parse_item(&reassembled.to_string()).expect("Reassembled function whi");
     mock_using_item.vis = syn::Visibility::Public;
 
     let item_ident = &mock_using_item.ident;
@@ -149,7 +284,7 @@ pub fn use_mocks(_: TokenStream, input: TokenStream) -> TokenStream {
 
     let mocks = handle_generate_mocks();
 
-    let generated_mock = (quote! {
+    let generated_mock = unmark_trait_objects((quote! {
         #[allow(unused_imports)]
         #item_vis use self::#mod_fn::#item_ident;
         mod #mod_fn {
@@ -167,10 +302,10 @@ pub fn use_mocks(_: TokenStream, input: TokenStream) -> TokenStream {
                 #(#mocks)*
             }
         }
-    }).to_string();
+    }).to_string().parse().unwrap());
 
-    debug(&item_ident, &generated_mock);
-    generated_mock.parse().unwrap()
+    debug(item_ident, &generated_mock.to_string());
+    generated_mock
 }
 
 fn insert_use_generated_mocks_into_modules(mod_items: &mut Vec<syn::Item>) {
@@ -220,18 +355,20 @@ enum MacroInvocationPos {
     NewMock(usize),
     Given(usize),
     ExpectInteractions(usize),
+    GivenScoped(usize),
+    ExpectInteractionsScoped(usize),
 }
 
 /// Find the next galvanic-mock macro invocation in the source string.
 ///
-/// Looks for `new_mock!``, `given!`, `expect_interactions!`, and `then_verify_interactions!`.
+/// Looks for `new_mock!``, `given!`, `expect_interactions!`, `given_scoped!`, and `expect_interactions_scoped!`.
 /// The `source` string must have been reassembled from a `TokenTree`.
 /// The `source` string is expected to start in a code context, i.e., not inside
 /// a string.
 fn find_next_mock_macro_invocation(source: &str) -> Option<MacroInvocationPos> {
     use MacroInvocationPos::*;
     // there must be a space between the macro name and the ! as the ! is a separate token in the tree
-    let macro_names = ["new_mock !", "given !", "expect_interactions !"];
+    let macro_names = ["new_mock !", "given !", "expect_interactions !", "given_scoped !", "expect_interactions_scoped !"];
     // not efficient but does the job
     macro_names.into_iter()
                .filter_map(|&mac| {
@@ -246,6 +383,8 @@ fn find_next_mock_macro_invocation(source: &str) -> Option<MacroInvocationPos> {
                    "new_mock !" => NewMock(pos),
                    "given !" => Given(pos),
                    "expect_interactions !" => ExpectInteractions(pos),
+                   "given_scoped !" => GivenScoped(pos),
+                   "expect_interactions_scoped !" => ExpectInteractionsScoped(pos),
                    _ => panic!("Unreachable. No variant for macro name: {}", mac)
                 }))
 }
@@ -0,0 +1,262 @@
+/* Copyright 2017 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use syn;
+use quote;
+
+use data::*;
+use generate::type_param_mapper::TypeParamMapper;
+
+/// Makes a free function mockable.
+///
+/// Under `cfg(test)` the function first checks if a mock has been installed for the current thread
+/// and delegates the call to it. Otherwise the original function body is executed.
+/// The mock must implement the generated `Mockable<FunctionName>` trait which is registered as mockable.
+pub fn handle_mockable_fn(item: &syn::Item, trait_location: Option<syn::Path>) -> quote::Tokens {
+    let (decl, unsafety, constness, abi, generics, block) = match item.node {
+        syn::ItemKind::Fn(ref decl, ref unsafety, ref constness, ref abi, ref generics, ref block) =>
+            (decl, unsafety, constness, abi, generics, block),
+        _ => panic!("Expecting a function definition.")
+    };
+    check_mockable_signature(constness, generics);
+
+    let trait_ident = mockable_trait_name_for(&item.ident);
+    let dispatcher = dispatcher_name_for(&item.ident);
+
+    let mut mocked_decl = (**decl).clone();
+    let (args, destructuring) = bind_args_to_idents(&mut mocked_decl);
+    let method = create_trait_method(&item.ident, unsafety, abi, &mocked_decl, generics, &TypeParamMapper::new());
+
+    let mut mocked_fn = item.clone();
+    mocked_fn.attrs.push(syn::parse_outer_attr("#[cfg(test)]").unwrap());
+    if let syn::ItemKind::Fn(ref mut decl, _, _, _, _, ref mut mocked_block) = mocked_fn.node {
+        **mocked_block = dispatching_block(&dispatcher, &trait_ident, &item.ident, &args, &destructuring, block);
+        **decl = mocked_decl;
+    }
+
+    let mockable_trait = implement_mockable_trait(&trait_ident, &dispatcher, std::slice::from_ref(&method));
+    register_mockable_trait(&trait_ident, trait_location, vec![method]);
+
+    quote! {
+        #[cfg(not(test))]
+        #item
+
+        #mocked_fn
+        #mockable_trait
+    }
+}
+
+/// Makes the methods of an inherent `impl` block mockable.
+///
+/// All methods are delegated to a mock installed for the current thread (if any) under `cfg(test)`.
+/// The mock must implement the generated `Mockable<TypeName>` trait which is registered as mockable.
+/// The trait contains a method for each (associated) method of the `impl` block taking `&self` as receiver.
+pub fn handle_mockable_impl(item: &syn::Item, trait_location: Option<syn::Path>) -> quote::Tokens {
+    let (generics, self_ty) = match item.node {
+        syn::ItemKind::Impl(_, _, ref generics, None, ref self_ty, _) => (generics, self_ty),
+        syn::ItemKind::Impl(_, _, _, Some(..), _, _) => panic!("#[mockable] on trait implementations is not supported. Apply it to the trait instead."),
+        _ => panic!("Expecting an impl block.")
+    };
+    if !generics.ty_params.is_empty() || !generics.lifetimes.is_empty() {
+        panic!("#[mockable] does not support generic impl blocks yet.");
+    }
+
+    let type_ident = match **self_ty {
+        syn::Ty::Path(None, ref path) => path.segments.last().expect("A type path without segment is not valid.").ident.clone(),
+        _ => panic!("#[mockable] impl blocks must be implemented for a named type.")
+    };
+    let trait_ident = mockable_trait_name_for(&type_ident);
+    let dispatcher = dispatcher_name_for(&type_ident);
+
+    let mut self_mapper = TypeParamMapper::new();
+    self_mapper.add_mapping(syn::Ident::from("Self"), (**self_ty).clone());
+
+    let mut mocked_impl = item.clone();
+    mocked_impl.attrs.push(syn::parse_outer_attr("#[cfg(test)]").unwrap());
+
+    let mut methods = Vec::new();
+    if let syn::ItemKind::Impl(_, _, _, _, _, ref mut mocked_items) = mocked_impl.node {
+        for impl_item in mocked_items.iter_mut() {
+            if let syn::ImplItemKind::Method(ref mut sig, ref mut block) = impl_item.node {
+                check_mockable_signature(&sig.constness, &sig.generics);
+                let (args, destructuring) = bind_args_to_idents(&mut sig.decl);
+                methods.push(create_trait_method(&impl_item.ident, &sig.unsafety, &sig.abi, &sig.decl, &sig.generics, &self_mapper));
+                *block = dispatching_block(&dispatcher, &trait_ident, &impl_item.ident, &args, &destructuring, block);
+            }
+        }
+    }
+
+    let mockable_trait = implement_mockable_trait(&trait_ident, &dispatcher, &methods);
+    register_mockable_trait(&trait_ident, trait_location, methods);
+
+    quote! {
+        #[cfg(not(test))]
+        #item
+
+        #mocked_impl
+        #mockable_trait
+    }
+}
+
+fn mockable_trait_name_for(ident: &syn::Ident) -> syn::Ident {
+    let camel_case = ident.as_ref().split('_').map(|part| {
+        let mut chars = part.chars();
+        match chars.next() {
+            Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+            None => String::new()
+        }
+    }).collect::<String>();
+    syn::Ident::from(format!("Mockable{}", camel_case))
+}
+
+fn dispatcher_name_for(ident: &syn::Ident) -> syn::Ident {
+    syn::Ident::from(format!("MOCK_DISPATCHER_FOR_{}", ident.as_ref().to_uppercase()))
+}
+
+fn installation_name_for(trait_ident: &syn::Ident) -> syn::Ident {
+    syn::Ident::from(format!("{}Installation", trait_ident))
+}
+
+fn check_mockable_signature(constness: &syn::Constness, generics: &syn::Generics) {
+    if let &syn::Constness::Const = constness {
+        panic!("#[mockable] does not support `const` functions.");
+    }
+    if !generics.ty_params.is_empty() {
+        panic!("#[mockable] does not support generic functions/methods yet.");
+    }
+}
+
+/// Creates the method of the mockable trait corresponding to a function.
+///
+/// The receiver of the function (if any) is replaced by `&self`, which refers to the mock.
+fn create_trait_method(ident: &syn::Ident, unsafety: &syn::Unsafety, abi: &Option<syn::Abi>,
+                       decl: &syn::FnDecl, generics: &syn::Generics, self_mapper: &TypeParamMapper) -> syn::TraitItem {
+    let mut inputs = vec![syn::FnArg::SelfRef(None, syn::Mutability::Immutable)];
+    inputs.extend(decl.inputs.iter().filter_map(|arg| match *arg {
+        syn::FnArg::Captured(ref pat, ref ty) => Some(syn::FnArg::Captured(pat.clone(), self_mapper.instantiate_from_ty(ty))),
+        syn::FnArg::Ignored(ref ty) => Some(syn::FnArg::Ignored(self_mapper.instantiate_from_ty(ty))),
+        _ => None
+    }));
+    let output = match decl.output {
+        syn::FunctionRetTy::Ty(ref ty) => syn::FunctionRetTy::Ty(self_mapper.instantiate_from_ty(ty)),
+        syn::FunctionRetTy::Default => syn::FunctionRetTy::Default
+    };
+
+    syn::TraitItem {
+        ident: ident.clone(),
+        attrs: Vec::new(),
+        node: syn::TraitItemKind::Method(syn::MethodSig {
+            unsafety: *unsafety,
+            constness: syn::Constness::NotConst,
+            abi: abi.clone(),
+            decl: syn::FnDecl { inputs, output, variadic: false },
+            generics: generics.clone()
+        }, None)
+    }
+}
+
+/// Binds all arguments of a function (except the receiver) to identifiers and returns them.
+///
+/// Arguments bound to other patterns are renamed to `mocked_arg<index>`.
+/// The returned statements destructure them again with the original patterns.
+fn bind_args_to_idents(decl: &mut syn::FnDecl) -> (Vec<syn::Ident>, Vec<quote::Tokens>) {
+    let mut args = Vec::new();
+    let mut destructuring = Vec::new();
+    for (idx, arg) in decl.inputs.iter_mut().enumerate() {
+        if let syn::FnArg::Captured(ref mut pat, _) = *arg {
+            if let syn::Pat::Ident(_, ref ident, None) = *pat {
+                if ident != "self" {
+                    args.push(ident.clone());
+                }
+                continue;
+            }
+
+            let ident = syn::Ident::from(format!("mocked_arg{}", idx));
+            let arg_pat = syn::Pat::Ident(syn::BindingMode::ByValue(syn::Mutability::Immutable), ident.clone(), None);
+            let orig_pat = std::mem::replace(pat, arg_pat);
+            destructuring.push(quote!(let #orig_pat = #ident;));
+            args.push(ident);
+        }
+    }
+    (args, destructuring)
+}
+
+/// Creates a function body which delegates to an installed mock before executing the original body.
+fn dispatching_block(dispatcher: &syn::Ident, trait_ident: &syn::Ident, method: &syn::Ident,
+                     args: &[syn::Ident], destructuring: &[quote::Tokens], block: &syn::Block) -> syn::Block {
+    let dispatching_expr = quote! {{
+        let maybe_mock = #dispatcher.with(|dispatcher| dispatcher.borrow().as_ref().and_then(|mock| mock.upgrade()));
+        if let Some(mock) = maybe_mock {
+            return #trait_ident::#method(&*mock, #(#args),*);
+        }
+        #(#destructuring)*
+        #block
+    }};
+
+    match syn::parse_expr(dispatching_expr.as_str()).expect("galvanic_mock internal error: unable to parse dispatching block").node {
+        syn::ExprKind::Block(_, block) => block,
+        _ => panic!("galvanic_mock internal error: dispatching block is not a block")
+    }
+}
+
+fn implement_mockable_trait(trait_ident: &syn::Ident, dispatcher: &syn::Ident, methods: &[syn::TraitItem]) -> quote::Tokens {
+    let installation = installation_name_for(trait_ident);
+    quote! {
+        #[cfg(test)]
+        pub(crate) trait #trait_ident {
+            #(#methods)*
+
+            /// Delegates all calls of the mocked functions/methods in the current thread to this mock
+            /// until the returned installation is dropped.
+            ///
+            /// Only a weak reference is kept, so calls are no longer delegated once the mock is dropped.
+            fn install_mock(self: ::std::rc::Rc<Self>) -> #installation where Self: Sized + 'static {
+                let mock = self as ::std::rc::Rc<dyn #trait_ident>;
+                let previous = #dispatcher.with(|dispatcher| dispatcher.borrow_mut().replace(::std::rc::Rc::downgrade(&mock)));
+                #installation { previous: previous }
+            }
+        }
+
+        /// Uninstalls the mock when dropped, restoring the mock installed before (if any).
+        #[cfg(test)]
+        #[must_use = "the mock is uninstalled immediately if the installation is not kept"]
+        pub(crate) struct #installation {
+            previous: Option<::std::rc::Weak<dyn #trait_ident>>,
+        }
+
+        #[cfg(test)]
+        impl ::std::ops::Drop for #installation {
+            fn drop(&mut self) {
+                let previous = self.previous.take();
+                #dispatcher.with(|dispatcher| *dispatcher.borrow_mut() = previous);
+            }
+        }
+
+        #[cfg(test)]
+        thread_local! {
+            static #dispatcher: ::std::cell::RefCell<Option<::std::rc::Weak<dyn #trait_ident>>> = ::std::cell::RefCell::new(None);
+        }
+    }
+}
+
+fn register_mockable_trait(trait_ident: &syn::Ident, trait_location: Option<syn::Path>, methods: Vec<syn::TraitItem>) {
+    let mut trait_path = trait_location.unwrap_or_else(|| syn::Path { global: false, segments: Vec::new() });
+    trait_path.segments.push(trait_ident.clone().into());
+
+    acquire!(MOCKABLE_TRAITS).insert(trait_path, TraitInfo::new(syn::Unsafety::Normal,
+                                                                syn::Generics::default(),
+                                                                Vec::new(),
+                                                                methods,
+                                                                Vec::new()));
+}
@@ -15,6 +15,7 @@
 use syn;
 use syn::parse::IResult;
 use data::*;
+use generate::is_receiver;
 
 named!(comma_separated_types -> Vec<syn::Path>,
     separated_nonempty_list!(punct!(","), syn::parse::path)
@@ -34,13 +35,75 @@ named!(parse_new_mock -> RequestedMock,
         do_parse!(keyword!("new_mock") >> punct!("!") >> punct!("(") >>
                   traits: call!(comma_separated_types) >>
                   attributes: many0!(outer_attr) >>
+                  fake_methods: option!(preceded!(keyword!("with_impl"), call!(syn::parse::tt))) >>
                   maybe_type_name: option!(preceded!(keyword!("for"), syn::parse::ident)) >>
                   punct!(")") >>
-                  (RequestedMock { traits, attributes, maybe_type_name })
+                  ({
+                      let is_nice = attributes.iter().any(|attr| is_word_attribute(attr, "nice"));
+                      let prefers_latest = attributes.iter().any(|attr| is_word_attribute(attr, "prefer_latest"));
+                      let spied_type = attributes.iter().filter_map(spied_type_of_attribute).next();
+                      let fixture = attributes.iter().filter_map(fixture_of_attribute).next();
+                      let attributes = attributes.into_iter().filter(|attr| {
+                          !is_word_attribute(attr, "nice") && !is_word_attribute(attr, "prefer_latest") && spied_type_of_attribute(attr).is_none() && fixture_of_attribute(attr).is_none()
+                      }).collect();
+                      let fake_methods = fake_methods.map_or(Vec::new(), |methods| parse_fake_methods(&methods));
+                      RequestedMock { traits, attributes, maybe_type_name, is_nice, prefers_latest, fake_methods, spied_type, fixture }
+                  })
         ), punct!(";")
     )
 );
 
+/// Checks for special attributes without arguments:
+/// `#[nice]` requests a mock returning default values for calls without a matching given behaviour,
+/// `#[prefer_latest]` requests a mock selecting the most recently added matching given behaviour.
+fn is_word_attribute(attribute: &syn::Attribute, word: &str) -> bool {
+    match attribute.value {
+        syn::MetaItem::Word(ref ident) => ident == word,
+        _ => false
+    }
+}
+
+/// `#[spy_on(Type)]` requests a mock forwarding calls to an object of `Type`.
+fn spied_type_of_attribute(attribute: &syn::Attribute) -> Option<syn::Ident> {
+    match attribute.value {
+        syn::MetaItem::List(ref ident, ref items) if ident == "spy_on" => match items.as_slice() {
+            &[syn::NestedMetaItem::MetaItem(syn::MetaItem::Word(ref spied_type))] => Some(spied_type.clone()),
+            _ => panic!("Expecting the name of the spied type: #[spy_on(Type)]")
+        },
+        _ => None
+    }
+}
+
+/// `#[record_to("path")]` and `#[replay_from("path")]` request a mock recording/replaying its calls.
+fn fixture_of_attribute(attribute: &syn::Attribute) -> Option<Fixture> {
+    match attribute.value {
+        syn::MetaItem::List(ref ident, ref items) if ident == "record_to" || ident == "replay_from" => {
+            let path = match items.as_slice() {
+                &[syn::NestedMetaItem::Literal(syn::Lit::Str(ref path, _))] => path.clone(),
+                _ => panic!("Expecting the path of the fixture file: #[{}(\"path/to/fixture.json\")]", ident)
+            };
+            Some(if ident == "record_to" { Fixture::Record(path) } else { Fixture::Replay(path) })
+        },
+        _ => None
+    }
+}
+
+/// Parses the block of methods following `with_impl`.
+fn parse_fake_methods(methods: &syn::TokenTree) -> Vec<syn::ImplItem> {
+    let impl_block = match syn::parse_item(&format!("impl FakeImplementation {}", quote!(#methods))) {
+        Ok(syn::Item { node: syn::ItemKind::Impl(_, _, _, None, _, impl_items), .. }) => impl_items,
+        _ => panic!("{}", "Expecting the methods of the fake implementation: with_impl { fn METHOD(&self, ...) -> ... { ... } ... }")
+    };
+
+    for item in impl_block.iter() {
+        match item.node {
+            syn::ImplItemKind::Method(ref signature, _) if signature.decl.inputs.iter().any(is_receiver) => {},
+            _ => panic!("The fake implementation of `with_impl` may only contain methods with a receiver: `{}` is not supported.", item.ident)
+        }
+    }
+    impl_block
+}
+
 pub fn handle_new_mock(source: &str, absolute_position: usize) -> (String, String) {
     if let IResult::Done(remainder, mut requested_mock) = parse_new_mock(source) {
         let mut requested_mocks = acquire!(REQUESTED_MOCKS);
@@ -55,6 +118,6 @@ pub fn handle_new_mock(source: &str, absolute_position: usize) -> (String, Strin
         return (assignment_stmt.to_string(), remainder.to_string());
     }
 
-    panic!(format!(concat!("Expecting a new_mock defintion of the form: new_mock!(paths::to::Traits, ... #[optional_attributes]...);\n",
-                           "\tGot: {}"), source));
+    panic!(concat!("Expecting a new_mock defintion of the form: new_mock!(paths::to::Traits, ... #[optional_attributes]... with_impl {{ optional methods }});\n",
+                  "\tGot: {}"), source);
 }
@@ -0,0 +1,125 @@
+/* Copyright 2017 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+#![cfg(not(feature = "galvanic_assert_integration"))]
+#![feature(proc_macro)]
+extern crate galvanic_mock;
+extern crate galvanic_assert;
+use galvanic_mock::{mockable, use_mocks};
+
+use std::collections::HashMap;
+
+#[mockable]
+trait Lookup {
+    fn name_of(&self, id: i32) -> Option<String>;
+}
+
+#[mockable]
+trait Repo {
+    fn get(&self, key: &str) -> Option<String>;
+    fn put(&self, key: &str, value: String);
+    fn count(&self) -> usize;
+}
+
+struct Token(i32);
+
+#[test]
+#[use_mocks]
+fn bind_values_without_type() {
+    let mock = new_mock!(Lookup);
+
+    given! {
+        bind names = vec![(1, "one"), (2, "two")].into_iter().collect::<HashMap<_, _>>();
+        bind is_known = |id: &i32| *id > 0 && *id < 3;
+        <mock as Lookup>::name_of(|id| (bound.is_known)(id)) then_return_from |&(id,)| bound.names.get(&id).map(|name| name.to_string()) always;
+        <mock as Lookup>::name_of(any()) then_return None always;
+    }
+
+    assert_eq!(mock.name_of(1), Some(String::from("one")));
+    assert_eq!(mock.name_of(2), Some(String::from("two")));
+    assert_eq!(mock.name_of(3), None);
+}
+
+#[test]
+#[use_mocks]
+fn bind_values_which_are_not_cloneable() {
+    let mock = new_mock!(Lookup);
+
+    given! {
+        bind token: Token = Token(7);
+        bind untyped_token = Token(8);
+        <mock as Lookup>::name_of(eq(bound.token.0)) then_return_from |_| Some(bound.untyped_token.0.to_string()) always;
+    }
+
+    assert_eq!(mock.name_of(7), Some(String::from("8")));
+}
+
+#[test]
+#[use_mocks]
+fn bind_values_without_type_in_expectations() {
+    let mock = new_mock!(Lookup);
+
+    given! {
+        <mock as Lookup>::name_of(any()) then_return None always;
+    }
+    expect_interactions! {
+        bind expected_ids = vec![1, 2];
+        <mock as Lookup>::name_of(|id| bound.expected_ids.contains(id)) times 2;
+    }
+
+    mock.name_of(1);
+    mock.name_of(2);
+    mock.name_of(3);
+    mock.verify();
+}
+
+#[test]
+#[use_mocks]
+fn update_mutable_bound_values() {
+    let mock = new_mock!(Lookup);
+
+    given! {
+        bind mut calls: usize = 0;
+        <mock as Lookup>::name_of(any()) then_return_from |&(id,)| {
+            *bound.calls.borrow_mut() += 1;
+            Some(format!("{} #{}", id, bound.calls.borrow()))
+        } always;
+    }
+
+    assert_eq!(mock.name_of(5), Some(String::from("5 #1")));
+    assert_eq!(mock.name_of(6), Some(String::from("6 #2")));
+}
+
+#[test]
+#[use_mocks]
+fn implement_stateful_fake_with_mutable_bound_values() {
+    let repo = new_mock!(Repo);
+
+    given! {
+        bind mut store = HashMap::new();
+        <repo as Repo>::put(any(), any()) then_return_from |&(key, ref value)| {
+            bound.store.borrow_mut().insert(key.to_string(), value.clone());
+        } always;
+        <repo as Repo>::get(any()) then_return_from |&(key,)| bound.store.borrow().get(key).cloned() always;
+        <repo as Repo>::count() then_return_from |_| bound.store.borrow().len() always;
+    }
+
+    assert_eq!(repo.get("a"), None);
+    repo.put("a", String::from("1"));
+    repo.put("b", String::from("2"));
+    repo.put("a", String::from("3"));
+    assert_eq!(repo.get("a"), Some(String::from("3")));
+    assert_eq!(repo.get("b"), Some(String::from("2")));
+    assert_eq!(repo.count(), 2);
+}
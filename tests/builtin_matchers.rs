@@ -0,0 +1,143 @@
+/* Copyright 2017 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+#![cfg(not(feature = "galvanic_assert_integration"))]
+#![feature(proc_macro)]
+extern crate galvanic_mock;
+extern crate galvanic_assert;
+use galvanic_mock::{mockable, use_mocks};
+
+#[mockable]
+trait TestTrait {
+    fn number(&self, x: i32) -> i32;
+    fn text(&self, s: String) -> i32;
+    fn list(&self, v: Vec<i32>) -> i32;
+    fn maybe(&self, x: Option<i32>) -> i32;
+    fn result(&self, x: Result<i32, String>) -> i32;
+}
+
+#[test]
+#[use_mocks]
+fn match_with_comparison_matchers() {
+    let mock = new_mock!(TestTrait);
+
+    given! {
+        <mock as TestTrait>::number(eq(1)) then_return 1 always;
+        <mock as TestTrait>::number(lt(0)) then_return 2 always;
+        <mock as TestTrait>::number(in_range(10..20)) then_return 3 always;
+        <mock as TestTrait>::number(gt(100)) then_return 4 always;
+        <mock as TestTrait>::number(ne(5)) then_return 5 always;
+        <mock as TestTrait>::number(any()) then_return 6 always;
+    }
+
+    assert_eq!(mock.number(1), 1);
+    assert_eq!(mock.number(-3), 2);
+    assert_eq!(mock.number(15), 3);
+    assert_eq!(mock.number(200), 4);
+    assert_eq!(mock.number(7), 5);
+    assert_eq!(mock.number(5), 6);
+}
+
+#[test]
+#[use_mocks]
+fn match_with_string_and_collection_matchers() {
+    let mock = new_mock!(TestTrait);
+
+    given! {
+        <mock as TestTrait>::text(starts_with("Hello")) then_return 1 always;
+        <mock as TestTrait>::text(contains('!')) then_return 2 always;
+        <mock as TestTrait>::text(eq("abc")) then_return 3 always;
+        <mock as TestTrait>::list(starts_with([1, 2])) then_return 1 always;
+        <mock as TestTrait>::list(contains(3)) then_return 2 always;
+    }
+
+    assert_eq!(mock.text("Hello world".to_string()), 1);
+    assert_eq!(mock.text("Bye!".to_string()), 2);
+    assert_eq!(mock.text("abc".to_string()), 3);
+    assert_eq!(mock.list(vec![1, 2, 5]), 1);
+    assert_eq!(mock.list(vec![5, 3]), 2);
+}
+
+#[test]
+#[use_mocks]
+fn match_with_variant_matchers() {
+    let mock = new_mock!(TestTrait);
+
+    given! {
+        <mock as TestTrait>::maybe(some(gt(1))) then_return 1 always;
+        <mock as TestTrait>::maybe(any()) then_return 2 always;
+        <mock as TestTrait>::result(ok(eq(1))) then_return 1 always;
+        <mock as TestTrait>::result(err(starts_with("fatal"))) then_return 2 always;
+        <mock as TestTrait>::result(any()) then_return 3 always;
+    }
+
+    assert_eq!(mock.maybe(Some(2)), 1);
+    assert_eq!(mock.maybe(Some(1)), 2);
+    assert_eq!(mock.maybe(None), 2);
+    assert_eq!(mock.result(Ok(1)), 1);
+    assert_eq!(mock.result(Err("fatal error".to_string())), 2);
+    assert_eq!(mock.result(Err("warning".to_string())), 3);
+}
+
+#[test]
+#[use_mocks]
+fn match_with_combining_matchers() {
+    let mock = new_mock!(TestTrait);
+
+    given! {
+        <mock as TestTrait>::number(all_of((gt(0), lt(10), not(eq(5))))) then_return 1 always;
+        <mock as TestTrait>::number(any_of((eq(5), eq(50)))) then_return 2 always;
+        <mock as TestTrait>::number(all_of((gt(100), |&x: &i32| x % 2 == 0))) then_return 3 always;
+        <mock as TestTrait>::number(any()) then_return 4 always;
+    }
+
+    assert_eq!(mock.number(3), 1);
+    assert_eq!(mock.number(5), 2);
+    assert_eq!(mock.number(50), 2);
+    assert_eq!(mock.number(102), 3);
+    assert_eq!(mock.number(101), 4);
+}
+
+#[test]
+#[use_mocks]
+fn use_matchers_in_expectations() {
+    let mock = new_mock!(TestTrait);
+
+    given! {
+        <mock as TestTrait>::number(any()) then_return 1 always;
+    }
+
+    expect_interactions! {
+        <mock as TestTrait>::number(in_range(1..4)) times 2;
+    }
+
+    mock.number(1);
+    mock.number(3);
+    mock.number(4);
+}
+
+#[test]
+#[use_mocks]
+fn describe_matchers() {
+    use self::mock::matchers::*;
+
+    assert_eq!(any().to_string(), "anything");
+    assert_eq!(eq(1).to_string(), "equal to 1");
+    assert_eq!(ne("a").to_string(), "not equal to \"a\"");
+    assert_eq!(in_range(1..5).to_string(), "in range 1..5");
+    assert_eq!(some(lt(2)).to_string(), "Some value less than 2");
+    assert_eq!(err(contains('x')).to_string(), "Err value containing 'x'");
+    assert_eq!(all_of((gt(1), not(eq(3)))).to_string(), "all of [greater than 1, not equal to 3]");
+    assert_eq!(any_of((ok(starts_with("a")),)).to_string(), "any of [Ok value starting with \"a\"]");
+}
@@ -0,0 +1,101 @@
+/* Copyright 2017 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+#![cfg(not(feature = "galvanic_assert_integration"))]
+#![feature(proc_macro)]
+extern crate galvanic_mock;
+extern crate galvanic_assert;
+use galvanic_mock::{mockable, use_mocks};
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[mockable]
+trait TestTrait {
+    fn func(&self, x: i32, y: &str) -> i32;
+}
+
+#[mockable]
+trait OtherTrait {
+    fn other(&self) -> bool;
+}
+
+#[test]
+#[use_mocks]
+fn hooks_receive_calls_in_order() {
+    let mock = new_mock!(TestTrait, OtherTrait);
+    let calls = Rc::new(RefCell::new(Vec::new()));
+    let log = calls.clone();
+    mock.on_call(move |trait_name, method, args| {
+        log.borrow_mut().push(format!("{}::{}({})", trait_name, method, args.join(", ")));
+    });
+
+    given! {
+        <mock as TestTrait>::func(any(), any()) then_return 1 always;
+        <mock as OtherTrait>::other() then_return true always;
+    }
+
+    mock.func(2, "x");
+    mock.other();
+
+    assert_eq!(*calls.borrow(), vec![String::from("TestTrait::func(2, \"x\")"), String::from("OtherTrait::other()")]);
+}
+
+#[test]
+#[use_mocks]
+fn hooks_run_before_behaviour_matching() {
+    let mock = new_mock!(TestTrait);
+    let num_calls = Rc::new(RefCell::new(0));
+    let counter = num_calls.clone();
+    mock.on_call(move |_, _, _| *counter.borrow_mut() += 1);
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| mock.func(1, "unmatched")));
+
+    assert!(result.is_err());
+    assert_eq!(*num_calls.borrow(), 1);
+}
+
+#[test]
+#[should_panic(expected = "negative argument")]
+#[use_mocks]
+fn hooks_can_assert_on_arguments() {
+    let mock = new_mock!(TestTrait #[nice]);
+    mock.on_call(|_, _, args| assert!(!args[0].starts_with('-'), "negative argument"));
+    mock.on_call(|_, _, _| {});
+
+    mock.func(1, "fine");
+    mock.func(-1, "not fine");
+}
+
+#[test]
+#[use_mocks]
+fn hooks_can_register_hooks() {
+    let mock = new_mock!(TestTrait #[nice]);
+    let mock = Rc::new(mock);
+    let calls = Rc::new(RefCell::new(Vec::new()));
+    let log = calls.clone();
+    let registering_mock = Rc::downgrade(&mock);
+    mock.on_call(move |_, _, args| {
+        let log = log.clone();
+        let first_arg = args[0].clone();
+        if let Some(mock) = registering_mock.upgrade() {
+            mock.on_call(move |_, _, args| log.borrow_mut().push(format!("{} after {}", args[0], first_arg)));
+        }
+    });
+
+    mock.func(1, "x");
+    mock.func(2, "y");
+
+    assert_eq!(*calls.borrow(), vec![String::from("2 after 1")]);
+}
@@ -0,0 +1,106 @@
+/* Copyright 2017 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+#![cfg(not(feature = "galvanic_assert_integration"))]
+#![feature(proc_macro)]
+extern crate galvanic_mock;
+extern crate galvanic_assert;
+use galvanic_mock::{mockable, use_mocks};
+
+#[mockable]
+trait Store {
+    fn get(&self, key: &str) -> Option<String>;
+    fn put(&self, key: String, value: i32) -> bool;
+    fn keys(&self) -> Box<Iterator<Item = String>>;
+}
+
+#[test]
+#[use_mocks]
+fn capture_local_variables_in_matchers_and_return_values() {
+    let mock = new_mock!(Store);
+    let known_key = "answer";
+    let answer = String::from("42");
+    let limit = 10;
+
+    given! {
+        <mock as Store>::get(|&key: &&str| key == known_key) then_return Some(answer.clone()) always;
+        <mock as Store>::get(any()) then_return None always;
+        <mock as Store>::put(any(), lt(limit)) then_return_from |&(ref key, value)| key.len() as i32 + value < limit always;
+    }
+
+    assert_eq!(mock.get("answer"), Some(String::from("42")));
+    assert_eq!(mock.get("question"), None);
+    assert!(mock.put(String::from("ab"), 5));
+    assert!(!mock.put(String::from("abcdef"), 5));
+}
+
+#[test]
+#[use_mocks]
+fn capture_local_variables_in_expectations() {
+    let mock = new_mock!(Store);
+    let expected_value = 3;
+
+    given! {
+        <mock as Store>::put(eq(String::from("x")), eq(expected_value)) then_return true always expect times 1;
+    }
+    expect_interactions! {
+        <mock as Store>::put(any(), gt(expected_value)) never;
+    }
+
+    assert!(mock.put(String::from("x"), 3));
+    mock.verify();
+}
+
+#[test]
+#[use_mocks]
+fn capture_local_variables_in_iterator_returns() {
+    let mock = new_mock!(Store);
+    let keys = vec![String::from("a"), String::from("b")];
+
+    given! {
+        <mock as Store>::keys() then_return_iter keys.clone() always;
+    }
+
+    assert_eq!(mock.keys().collect::<Vec<_>>(), vec!["a", "b"]);
+    assert_eq!(mock.keys().count(), 2);
+}
+
+#[test]
+#[use_mocks]
+fn describe_matchers_with_captured_values() {
+    let mock = new_mock!(Store);
+    let limit = 10;
+
+    given! {
+        <mock as Store>::put(any(), lt(limit)) then_return true always;
+    }
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| mock.put(String::from("x"), 11)));
+    let message = *result.expect_err("the call should fail").downcast::<String>().expect("expected a message");
+    assert!(message.contains("Store::put(anything, less than 10)"), "unexpected message: {}", message);
+}
+
+#[test]
+#[use_mocks]
+fn still_support_bound_values() {
+    let mock = new_mock!(Store);
+    let offset = 1;
+
+    given! {
+        bind value: i32 = 2;
+        <mock as Store>::put(any(), eq(bound.value)) then_return_from |&(_, value)| value + offset == 3 always;
+    }
+
+    assert!(mock.put(String::from("x"), 2));
+}
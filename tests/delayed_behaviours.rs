@@ -0,0 +1,101 @@
+/* Copyright 2017 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+#![cfg(not(feature = "galvanic_assert_integration"))]
+#![feature(proc_macro)]
+extern crate galvanic_mock;
+extern crate galvanic_assert;
+use galvanic_mock::{mockable, use_mocks};
+
+use std::sync::{Arc, Barrier};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[mockable]
+trait Client {
+    fn fetch(&self, id: i32) -> Result<String, String>;
+    fn ping(&self);
+}
+
+#[test]
+#[use_mocks]
+fn sleep_before_returning_value() {
+    let mock = new_mock!(Client);
+    let latency = Duration::from_millis(20);
+
+    given! {
+        <mock as Client>::fetch(eq(1)) then_return Ok(String::from("slow")) then_sleep latency always;
+        <mock as Client>::fetch(any()) then_return Ok(String::from("fast")) always;
+    }
+
+    let start = Instant::now();
+    assert_eq!(mock.fetch(1), Ok(String::from("slow")));
+    assert!(start.elapsed() >= latency);
+    assert_eq!(mock.fetch(2), Ok(String::from("fast")));
+}
+
+#[test]
+#[use_mocks]
+fn sleep_in_void_method() {
+    let mock = new_mock!(Client);
+
+    given! {
+        <mock as Client>::ping() then_sleep Duration::from_millis(10) always;
+    }
+
+    let start = Instant::now();
+    mock.ping();
+    assert!(start.elapsed() >= Duration::from_millis(10));
+}
+
+#[test]
+#[use_mocks]
+fn sleep_combined_with_fault_injection() {
+    let mock = new_mock!(Client);
+
+    given! {
+        <mock as Client>::fetch(any()) then_return Ok(String::new()) then_sleep Duration::from_millis(1) then_fail_after 1 with "timeout" always;
+    }
+
+    assert_eq!(mock.fetch(1), Ok(String::new()));
+    assert_eq!(mock.fetch(1), Err(String::from("timeout")));
+}
+
+#[test]
+#[use_mocks]
+fn block_until_other_thread_reaches_barrier() {
+    let mock = new_mock!(Client);
+    let barrier = Arc::new(Barrier::new(2));
+    let is_released = Arc::new(AtomicBool::new(false));
+    let worker_barrier = barrier.clone();
+
+    given! {
+        <mock as Client>::fetch(any()) then_return Ok(String::from("released")) then_block_until barrier always;
+    }
+
+    let worker = {
+        let barrier = worker_barrier;
+        let is_released = is_released.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            is_released.store(true, Ordering::SeqCst);
+            barrier.wait();
+        })
+    };
+
+    assert_eq!(mock.fetch(1), Ok(String::from("released")));
+    assert!(is_released.load(Ordering::SeqCst));
+    worker.join().unwrap();
+}
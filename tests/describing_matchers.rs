@@ -0,0 +1,138 @@
+/* Copyright 2017 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+#![cfg(not(feature = "galvanic_assert_integration"))]
+#![feature(proc_macro)]
+extern crate galvanic_mock;
+extern crate galvanic_assert;
+use galvanic_mock::{mockable, use_mocks};
+
+#[mockable]
+trait TestTrait {
+    fn func(&self, x: i32, y: i32) -> i32;
+    fn maybe(&self, x: Option<i32>) -> i32;
+    fn label(&self, x: i32) -> &str;
+}
+
+#[test]
+#[should_panic(expected = "TestTrait::func(less than 2, | _ | true) then_return 1 always (unmatched arguments: #0)")]
+#[use_mocks]
+fn describe_builtin_matchers_of_unmatched_given_statements() {
+    let mock = new_mock!(TestTrait);
+
+    given! {
+        <mock as TestTrait>::func(lt(2), |_| true) then_return 1 always;
+    }
+
+    mock.func(5, 2);
+}
+
+#[test]
+#[should_panic(expected = "Behaviour unsatisfied: TestTrait::maybe(Some value in range 1..4) times 2")]
+#[use_mocks]
+fn describe_builtin_matchers_of_unsatisfied_expect_statements() {
+    let mock = new_mock!(TestTrait);
+
+    given! {
+        <mock as TestTrait>::maybe(any()) then_return 1 always;
+    }
+
+    expect_interactions! {
+        <mock as TestTrait>::maybe(some(in_range(1..4))) times 2;
+    }
+
+    mock.maybe(Some(1));
+}
+
+#[test]
+#[should_panic(expected = "TestTrait::func(| & a | a < 2, | _ | true) then_return 1 always")]
+#[use_mocks]
+fn describe_closures_by_their_source() {
+    let mock = new_mock!(TestTrait);
+
+    given! {
+        <mock as TestTrait>::func(|&a| a < 2, |_| true) then_return 1 always;
+    }
+
+    mock.func(5, 2);
+}
+
+#[use_mocks]
+mod custom_matchers {
+    use super::TestTrait;
+
+    pub struct IsEven;
+
+    impl<'a> mock::ArgMatcher<'a, i32> for IsEven {
+        fn match_args(&self, actual: &'a i32) -> bool { actual % 2 == 0 }
+
+        fn describe(&self) -> Option<String> { Some(String::from("an even integer")) }
+    }
+
+    /// Matches nothing but tells which instance described and explained the mismatch.
+    pub struct Numbered(usize);
+
+    impl Numbered {
+        pub fn next(instances: &std::cell::Cell<usize>) -> Self {
+            instances.set(instances.get() + 1);
+            Numbered(instances.get())
+        }
+    }
+
+    impl<'a> mock::ArgMatcher<'a, i32> for Numbered {
+        fn match_args(&self, _: &'a i32) -> bool { false }
+
+        fn describe(&self) -> Option<String> { Some(format!("instance #{}", self.0)) }
+
+        fn explain_mismatch(&self, _: &'a i32) -> Option<String> { Some(format!("checked by instance #{}", self.0)) }
+    }
+
+    #[test]
+    #[should_panic(expected = "TestTrait::func(an even integer, not an even integer) then_return 1 always (unmatched arguments: #1)")]
+    fn describe_custom_matchers() {
+        let mock = new_mock!(TestTrait);
+
+        given! {
+            <mock as TestTrait>::func(custom_matchers::IsEven, not(custom_matchers::IsEven)) then_return 1 always;
+        }
+
+        mock.func(2, 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "TestTrait::func(instance #1, | _ | true) then_return 1 always (unmatched arguments: #0 (checked by instance #1))")]
+    fn describe_statements_by_the_matchers_used_for_matching() {
+        let mock = new_mock!(TestTrait);
+        let instances = std::cell::Cell::new(0);
+
+        given! {
+            <mock as TestTrait>::func(custom_matchers::Numbered::next(&instances), |_| true) then_return 1 always;
+        }
+
+        mock.func(5, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "TestTrait::label(instance #1) then_return \"one\" always (unmatched arguments: #0 (checked by instance #1))")]
+    fn describe_statements_not_capturing_their_environment_by_the_matchers_used_for_matching() {
+        let mock = new_mock!(TestTrait);
+
+        given! {
+            bind instances: std::cell::Cell<usize> = std::cell::Cell::new(0);
+            <mock as TestTrait>::label(custom_matchers::Numbered::next(&bound.instances)) then_return "one" always;
+        }
+
+        mock.label(5);
+    }
+}
@@ -0,0 +1,139 @@
+/* Copyright 2017 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+#![cfg(not(feature = "galvanic_assert_integration"))]
+#![feature(proc_macro)]
+extern crate galvanic_mock;
+extern crate galvanic_assert;
+use galvanic_mock::{mockable, use_mocks};
+
+use std::io;
+
+#[derive(Debug, PartialEq)]
+pub enum StoreError {
+    Unavailable,
+    Corrupt(String)
+}
+
+#[mockable]
+trait Storage {
+    fn read(&self, key: i32) -> io::Result<String>;
+    fn write(&self, key: i32, value: String) -> Result<(), StoreError>;
+}
+
+fn outcomes<T, E>(results: Vec<Result<T, E>>) -> Vec<bool> {
+    results.into_iter().map(|result| result.is_ok()).collect()
+}
+
+#[test]
+#[use_mocks]
+fn fail_with_converts_error() {
+    let mock = new_mock!(Storage);
+
+    given! {
+        <mock as Storage>::read(eq(1)) then_fail_with io::ErrorKind::NotFound always;
+        <mock as Storage>::write(any(), any()) then_fail_with StoreError::Corrupt(String::from("bad block")) always;
+    }
+
+    assert_eq!(mock.read(1).unwrap_err().kind(), io::ErrorKind::NotFound);
+    assert_eq!(mock.write(1, String::from("x")), Err(StoreError::Corrupt(String::from("bad block"))));
+}
+
+#[test]
+#[use_mocks]
+fn fail_with_captures_environment() {
+    let mock = new_mock!(Storage);
+    let error = StoreError::Unavailable;
+    let message = format!("{:?}", error);
+
+    given! {
+        <mock as Storage>::read(any()) then_fail_with io::Error::new(io::ErrorKind::Other, message.clone()) always;
+    }
+
+    assert_eq!(mock.read(1).unwrap_err().to_string(), "Unavailable");
+}
+
+#[test]
+#[use_mocks]
+fn fail_after_number_of_calls() {
+    let mock = new_mock!(Storage);
+
+    given! {
+        <mock as Storage>::write(any(), any()) then_return Ok(()) then_fail_after 2 with StoreError::Unavailable always;
+    }
+
+    let results = (0..4).map(|key| mock.write(key, String::new())).collect::<Vec<_>>();
+    assert_eq!(results, vec![Ok(()), Ok(()), Err(StoreError::Unavailable), Err(StoreError::Unavailable)]);
+}
+
+#[test]
+#[use_mocks]
+fn fail_after_number_of_calls_captures_environment() {
+    let mock = new_mock!(Storage);
+    let data = String::from("data");
+    let reason = String::from("disk gone");
+
+    given! {
+        <mock as Storage>::read(any()) then_return Ok(data.clone()) then_fail_after 1 with io::Error::new(io::ErrorKind::Other, reason.clone()) always;
+    }
+
+    assert_eq!(mock.read(1).unwrap(), "data");
+    assert_eq!(mock.read(2).unwrap_err().to_string(), "disk gone");
+    assert_eq!(mock.read(3).unwrap_err().to_string(), "disk gone");
+}
+
+#[test]
+#[use_mocks]
+fn fail_on_selected_calls() {
+    let mock = new_mock!(Storage);
+
+    given! {
+        <mock as Storage>::read(any()) then_return Ok(String::from("data")) then_fail_on vec![2, 4] with io::ErrorKind::TimedOut always;
+    }
+
+    let results = (0..5).map(|key| mock.read(key)).collect::<Vec<_>>();
+    assert_eq!(outcomes(results), vec![true, false, true, false, true]);
+}
+
+#[test]
+#[use_mocks]
+fn fail_randomly_is_deterministic() {
+    let first_mock = new_mock!(Storage);
+    let second_mock = new_mock!(Storage);
+
+    given! {
+        <first_mock as Storage>::read(any()) then_return Ok(String::new()) then_fail_randomly 0.5 seed 42 with io::ErrorKind::Interrupted always;
+        <second_mock as Storage>::read(any()) then_return Ok(String::new()) then_fail_randomly 0.5 seed 42 with io::ErrorKind::Interrupted always;
+    }
+
+    let first_outcomes = outcomes((0..100).map(|key| first_mock.read(key)).collect());
+    let second_outcomes = outcomes((0..100).map(|key| second_mock.read(key)).collect());
+    let num_failures = first_outcomes.iter().filter(|is_ok| !**is_ok).count();
+    assert_eq!(first_outcomes, second_outcomes);
+    assert!(num_failures > 30 && num_failures < 70, "unexpected number of failures: {}", num_failures);
+}
+
+#[test]
+#[use_mocks]
+fn fail_randomly_with_extreme_fractions() {
+    let mock = new_mock!(Storage);
+
+    given! {
+        <mock as Storage>::read(eq(0)) then_return Ok(String::new()) then_fail_randomly 0.0 seed 7 with io::ErrorKind::Other always;
+        <mock as Storage>::read(eq(1)) then_return Ok(String::new()) then_fail_randomly 1.0 seed 7 with io::ErrorKind::Other always;
+    }
+
+    assert!((0..20).all(|_| mock.read(0).is_ok()));
+    assert!((0..20).all(|_| mock.read(1).is_err()));
+}
@@ -0,0 +1,88 @@
+/* Copyright 2017 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+#![feature(proc_macro)]
+extern crate galvanic_mock;
+extern crate galvanic_assert;
+use galvanic_mock::{mockable, use_mocks};
+
+#[mockable]
+trait Repo {
+    fn get(&self, key: &str) -> Option<String>;
+    fn count(&self) -> usize;
+}
+
+#[mockable]
+trait Clock {
+    fn now(&self) -> u64;
+}
+
+#[test]
+#[use_mocks]
+fn fake_is_called_without_given_behaviour() {
+    let mock = new_mock!(Repo with_impl {
+        fn get(&self, key: &str) -> Option<String> {
+            if key.starts_with("known") { Some(key.to_uppercase()) } else { None }
+        }
+    });
+
+    assert_eq!(mock.get("known_key"), Some(String::from("KNOWN_KEY")));
+    assert_eq!(mock.get("other_key"), None);
+}
+
+#[test]
+#[use_mocks]
+fn given_behaviour_overrides_fake() {
+    let mock = new_mock!(Repo with_impl {
+        fn get(&self, key: &str) -> Option<String> { Some(key.to_string()) }
+    });
+
+    given! {
+        <mock as Repo>::get(|key: &&str| *key == "special") then_return Some(String::from("overridden")) always;
+    }
+
+    assert_eq!(mock.get("special"), Some(String::from("overridden")));
+    assert_eq!(mock.get("plain"), Some(String::from("plain")));
+}
+
+#[test]
+#[use_mocks]
+fn fake_calls_are_expected() {
+    let mock = new_mock!(Repo, Clock #[nice] with_impl {
+        fn get(&self, key: &str) -> Option<String> { Some(key.to_string()) }
+        fn now(&self) -> u64 { 42 }
+    } for FakeRepo);
+
+    expect_interactions! {
+        <mock as Repo>::get(|key: &&str| *key == "a") times 2;
+        <mock as Clock>::now() times 1;
+    }
+
+    assert_eq!(mock.get("a"), Some(String::from("a")));
+    assert_eq!(mock.get("a"), Some(String::from("a")));
+    assert_eq!(mock.now(), 42);
+    assert_eq!(mock.count(), 0);
+    mock.verify();
+}
+
+#[test]
+#[should_panic]
+#[use_mocks]
+fn method_without_fake_panics() {
+    let mock = new_mock!(Repo with_impl {
+        fn get(&self, _key: &str) -> Option<String> { None }
+    });
+
+    mock.count();
+}
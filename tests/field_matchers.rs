@@ -0,0 +1,138 @@
+/* Copyright 2017 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+#![cfg(not(feature = "galvanic_assert_integration"))]
+#![feature(proc_macro)]
+extern crate galvanic_mock;
+extern crate galvanic_assert;
+use galvanic_mock::{mockable, use_mocks};
+
+#[derive(Debug)]
+pub struct Address {
+    pub city: String,
+    pub zip: u32
+}
+
+#[derive(Debug)]
+pub struct Request {
+    pub id: u32,
+    pub user: String,
+    pub address: Address,
+    pub tags: Vec<String>
+}
+
+#[mockable]
+trait Service {
+    fn handle(&self, request: Request) -> i32;
+    fn handle_ref(&self, request: &Request) -> i32;
+    fn handle_maybe(&self, request: Option<Request>) -> i32;
+}
+
+fn request(id: u32, user: &str, city: &str) -> Request {
+    Request {
+        id: id,
+        user: user.to_string(),
+        address: Address { city: city.to_string(), zip: 1010 },
+        tags: vec!["urgent".to_string()]
+    }
+}
+
+#[test]
+#[use_mocks]
+fn match_fields_with_has_field() {
+    let mock = new_mock!(Service);
+
+    given! {
+        <mock as Service>::handle(has_field!(id, eq(1))) then_return 1 always;
+        <mock as Service>::handle(has_field!(id, |&id| id > 100)) then_return 5 always;
+        <mock as Service>::handle(has_field!(address, has_field!(city, starts_with("Vie")))) then_return 2 always;
+        <mock as Service>::handle(all_of((has_field!(user, eq("bob")), not(has_field!(tags, contains("urgent")))))) then_return 3 always;
+        <mock as Service>::handle(any()) then_return 4 always;
+    }
+
+    assert_eq!(mock.handle(request(1, "alice", "Graz")), 1);
+    assert_eq!(mock.handle(request(101, "alice", "Graz")), 5);
+    assert_eq!(mock.handle(request(2, "alice", "Vienna")), 2);
+    assert_eq!(mock.handle(request(2, "bob", "Graz")), 4);
+    assert_eq!(mock.handle(Request { tags: Vec::new(), ..request(2, "bob", "Graz") }), 3);
+}
+
+#[test]
+#[use_mocks]
+fn match_fields_with_matches_struct() {
+    let mock = new_mock!(Service);
+
+    given! {
+        <mock as Service>::handle(matches_struct!(Request { id: gt(10), user: eq("bob"), .. })) then_return 1 always;
+        <mock as Service>::handle_ref(matches_struct!(Request { address: matches_struct!(Address { zip: in_range(1000..2000), .. }), .. })) then_return 2 always;
+        <mock as Service>::handle_maybe(some(matches_struct!(Request { address: has_field!(city, eq("Graz")), .. }))) then_return 3 always;
+        <mock as Service>::handle(any()) then_return 4 always;
+        <mock as Service>::handle_ref(any()) then_return 5 always;
+        <mock as Service>::handle_maybe(any()) then_return 6 always;
+    }
+
+    assert_eq!(mock.handle(request(11, "bob", "Graz")), 1);
+    assert_eq!(mock.handle(request(11, "alice", "Graz")), 4);
+    assert_eq!(mock.handle_ref(&request(1, "alice", "Graz")), 2);
+    assert_eq!(mock.handle_maybe(Some(request(1, "alice", "Graz"))), 3);
+    assert_eq!(mock.handle_maybe(Some(request(1, "alice", "Vienna"))), 6);
+}
+
+#[test]
+#[use_mocks]
+fn use_field_matchers_in_expectations() {
+    let mock = new_mock!(Service);
+
+    given! {
+        <mock as Service>::handle(any()) then_return 1 always;
+    }
+
+    expect_interactions! {
+        <mock as Service>::handle(has_field!(user, eq("bob"))) times 2;
+    }
+
+    mock.handle(request(1, "bob", "Graz"));
+    mock.handle(request(2, "bob", "Graz"));
+    mock.handle(request(3, "alice", "Graz"));
+}
+
+#[test]
+#[should_panic(expected = "Service::handle(Request with field `id` greater than 10, with field `address` with field `city` equal to \"Linz\") then_return 1 always (unmatched arguments: #0 (field `address`: field `city` was \"Graz\", expected equal to \"Linz\"))")]
+#[use_mocks]
+fn explain_mismatching_fields() {
+    let mock = new_mock!(Service);
+
+    given! {
+        <mock as Service>::handle(matches_struct!(Request { id: gt(10), address: has_field!(city, eq("Linz")), .. })) then_return 1 always;
+    }
+
+    mock.handle(request(11, "bob", "Graz"));
+}
+
+#[test]
+#[should_panic(expected = "Behaviour unsatisfied: Service::handle(with field `user` equal to \"bob\") times 1")]
+#[use_mocks]
+fn describe_field_matchers_of_unsatisfied_expectations() {
+    let mock = new_mock!(Service);
+
+    given! {
+        <mock as Service>::handle(any()) then_return 1 always;
+    }
+
+    expect_interactions! {
+        <mock as Service>::handle(has_field!(user, eq("bob"))) times 1;
+    }
+
+    mock.handle(request(1, "alice", "Graz"));
+}
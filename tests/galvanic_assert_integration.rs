@@ -48,3 +48,44 @@ fn test_explicit_matcher_with_galvanic_assert_matcher() {
 
     assert_eq!(mock.func(2, 3.3), 12);
 }
+
+#[test]
+#[use_mocks]
+fn test_galvanic_assert_matchers_are_not_shadowed_by_builtin_matchers() {
+    let mock = new_mock!(TestTrait);
+
+    given! {
+        <mock as TestTrait>::func(lt(2), gt(3.3)) then_return 12 always;
+    }
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| mock.func(5, 4.4)));
+    let message = result.err()
+                        .and_then(|payload| payload.downcast_ref::<String>().cloned())
+                        .expect("the unmatched call should panic with a message");
+    assert!(!message.contains("less than 2"), "built-in matcher was used: {}", message);
+}
+
+#[test]
+#[use_mocks]
+fn test_builtin_matchers_are_available_with_module_prefix() {
+    let mock = new_mock!(TestTrait);
+
+    given! {
+        <mock as TestTrait>::func(matchers::lt(2), matchers::any()) then_return 12 always;
+    }
+
+    assert_eq!(mock.func(1, 4.4), 12);
+}
+
+#[test]
+#[should_panic(expected = "TestTrait::func(lt ( 2 ), gt ( 3.3 )) then_return 12 always (unmatched arguments: #0 (less_than: Expected: 2, Got: 5))")]
+#[use_mocks]
+fn test_explain_mismatch_of_galvanic_assert_matchers() {
+    let mock = new_mock!(TestTrait);
+
+    given! {
+        <mock as TestTrait>::func(lt(2), gt(3.3)) then_return 12 always;
+    }
+
+    mock.func(5, 4.4);
+}
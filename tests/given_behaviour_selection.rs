@@ -0,0 +1,109 @@
+/* Copyright 2017 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+#![cfg(not(feature = "galvanic_assert_integration"))]
+#![feature(proc_macro)]
+extern crate galvanic_mock;
+extern crate galvanic_assert;
+use galvanic_mock::{mockable, use_mocks};
+
+#[mockable]
+trait TestTrait {
+    fn func(&self, x: i32) -> i32;
+}
+
+#[use_mocks]
+mod prefer_latest {
+    use super::TestTrait;
+
+    fn create_mock() -> mock::LatestMock {
+        let mock = new_mock!(TestTrait #[prefer_latest] for LatestMock);
+
+        given! {
+            <mock as TestTrait>::func(any()) then_return 12 always;
+        }
+
+        mock
+    }
+
+    #[test]
+    fn later_behaviour_overrides_factory_behaviour() {
+        let mock = create_mock();
+
+        given! {
+            <mock as TestTrait>::func(eq(1)) then_return 1 times 1;
+        }
+
+        assert_eq!(mock.func(1), 1);
+        assert_eq!(mock.func(1), 12);
+        assert_eq!(mock.func(2), 12);
+    }
+
+    #[test]
+    fn policy_can_be_changed_at_runtime() {
+        let mut mock = create_mock();
+
+        given! {
+            <mock as TestTrait>::func(any()) then_return 2 always;
+        }
+
+        assert_eq!(mock.func(1), 2);
+        mock.prefer_latest_given_behaviours(false);
+        assert_eq!(mock.func(1), 12);
+    }
+}
+
+#[test]
+#[use_mocks]
+fn first_matching_behaviour_is_selected_by_default() {
+    let mock = new_mock!(TestTrait);
+
+    given! {
+        <mock as TestTrait>::func(any()) then_return 12 always;
+        <mock as TestTrait>::func(eq(1)) then_return 1 always;
+    }
+
+    assert_eq!(mock.func(1), 12);
+}
+
+#[test]
+#[use_mocks]
+fn behaviour_with_higher_priority_is_selected() {
+    let mock = new_mock!(TestTrait);
+
+    given! {
+        <mock as TestTrait>::func(any()) then_return 12 always;
+        <mock as TestTrait>::func(eq(1)) then_return 1 times 1 with_priority 1;
+        <mock as TestTrait>::func(eq(2)) then_return -1 always with_priority -1;
+        <mock as TestTrait>::func(eq(2)) then_return 2 always expect times 1 with_priority 1;
+    }
+
+    assert_eq!(mock.func(1), 1);
+    assert_eq!(mock.func(1), 12);
+    assert_eq!(mock.func(2), 2);
+    mock.verify();
+}
+
+#[test]
+#[use_mocks]
+fn priorities_take_precedence_over_policy() {
+    let mock = new_mock!(TestTrait #[prefer_latest]);
+
+    given! {
+        <mock as TestTrait>::func(any()) then_return 12 always with_priority 5;
+        <mock as TestTrait>::func(any()) then_return 1 always;
+    }
+
+    assert_eq!(mock.func(1), 12);
+}
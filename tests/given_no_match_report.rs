@@ -0,0 +1,113 @@
+/* Copyright 2017 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+#![feature(proc_macro)]
+extern crate galvanic_mock;
+extern crate galvanic_assert;
+use galvanic_mock::{mockable, use_mocks};
+
+#[mockable]
+trait TestTrait {
+    fn func(&self, x: i32, y: i32) -> i32;
+    fn explicit(&self, x: i32) -> i32;
+}
+
+#[test]
+#[should_panic(expected = "No matching given! statement found for call func(5, 6) among the remaining ones:")]
+#[use_mocks]
+fn report_actual_arguments() {
+    let mock = new_mock!(TestTrait);
+
+    given! {
+        <mock as TestTrait>::func(|&a| a < 2, |&b| b < 2) then_return 1 always;
+    }
+
+    mock.func(5, 6);
+}
+
+#[test]
+#[should_panic(expected = "then_return 1 always (unmatched arguments: #1)")]
+#[use_mocks]
+fn report_unmatched_argument_indices() {
+    let mock = new_mock!(TestTrait);
+
+    given! {
+        <mock as TestTrait>::func(|&a| a < 2, |&b| b < 2) then_return 1 always;
+    }
+
+    mock.func(1, 6);
+}
+
+#[test]
+#[should_panic(expected = "then_return 1 always (argument matcher not satisfied)")]
+#[use_mocks]
+fn report_unsatisfied_explicit_matcher() {
+    let mock = new_mock!(TestTrait);
+
+    given! {
+        <mock as TestTrait>::explicit |&(a,)| a < 2 then_return 1 always;
+    }
+
+    mock.explicit(5);
+}
+
+#[test]
+#[should_panic(expected = "Exhausted given! statements which would have matched:\n\tTestTrait::func(| _ | true, | _ | true) then_return 1 times ( 1 )")]
+#[use_mocks]
+fn report_exhausted_behaviours() {
+    let mock = new_mock!(TestTrait);
+
+    given! {
+        <mock as TestTrait>::func(|_| true, |_| true) then_return 1 times(1);
+    }
+
+    assert_eq!(mock.func(1, 2), 1);
+    mock.func(1, 2);
+}
+
+#[test]
+#[use_mocks]
+fn do_not_evaluate_matchers_of_exhausted_behaviours() {
+    let mock = new_mock!(TestTrait);
+    let evaluations = std::rc::Rc::new(std::cell::Cell::new(0));
+
+    given! {
+        bind evaluations: std::rc::Rc<std::cell::Cell<i32>> = evaluations.clone();
+        <mock as TestTrait>::func(|_| { bound.evaluations.set(bound.evaluations.get() + 1); true }, |_| true) then_return 1 times(1);
+        <mock as TestTrait>::func(|_| true, |_| true) then_return 2 always;
+    }
+
+    assert_eq!(mock.func(1, 2), 1);
+    assert_eq!(mock.func(1, 2), 2);
+    assert_eq!(mock.func(1, 2), 2);
+    assert_eq!(evaluations.get(), 1);
+}
+
+#[test]
+#[use_mocks]
+fn stop_evaluating_matchers_after_the_first_unsatisfied_one() {
+    let mock = new_mock!(TestTrait);
+    let evaluations = std::rc::Rc::new(std::cell::Cell::new(0));
+
+    given! {
+        bind evaluations: std::rc::Rc<std::cell::Cell<i32>> = evaluations.clone();
+        <mock as TestTrait>::func(|&a| a < 2, |_| { bound.evaluations.set(bound.evaluations.get() + 1); true }) then_return 1 always;
+        <mock as TestTrait>::func(|_| true, |_| true) then_return 2 always;
+    }
+
+    assert_eq!(mock.func(5, 2), 2);
+    assert_eq!(evaluations.get(), 0);
+    assert_eq!(mock.func(1, 2), 1);
+    assert_eq!(evaluations.get(), 1);
+}
@@ -0,0 +1,68 @@
+/* Copyright 2017 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+#![feature(proc_macro)]
+extern crate galvanic_mock;
+extern crate galvanic_assert;
+use galvanic_mock::{mockable, use_mocks};
+
+#[mockable]
+trait TestTrait {
+    fn func(&self, x: i32) -> i32;
+}
+
+#[test]
+#[use_mocks]
+fn return_value_and_expect_interaction() {
+    let mock = new_mock!(TestTrait);
+
+    given! {
+        bind value: i32 = 1;
+        <mock as TestTrait>::func(|&a| a == bound.value) then_return 2 expect times 1;
+        <mock as TestTrait>::func(|_| true) then_return 3 times 2 expect at_least 1;
+    }
+
+    assert_eq!(mock.func(1), 2);
+    assert_eq!(mock.func(5), 3);
+
+    let report = mock.verification_report();
+    assert!(report.is_satisfied());
+    assert_eq!(report.expectations.len(), 2);
+}
+
+#[test]
+#[should_panic(expected = "Behaviour unsatisfied: TestTrait::func(| & a | a == 1) times 1")]
+#[use_mocks]
+fn verify_expectation_of_given_statement() {
+    let mock = new_mock!(TestTrait);
+
+    given! {
+        <mock as TestTrait>::func(|&a| a == 1) then_return 2 expect times 1;
+    }
+}
+
+#[test]
+#[should_panic(expected = "Behaviour unsatisfied: TestTrait::func(| & a | a == 1) times 2")]
+#[use_mocks]
+fn verify_expectation_of_scoped_given_statement() {
+    let mut mock = new_mock!(TestTrait);
+    mock.should_verify_on_drop(false);
+
+    {
+        let _guard = given_scoped! {
+            <mock as TestTrait>::func(|&a| a == 1) then_return 2 expect times 2;
+        };
+        assert_eq!(mock.func(1), 2);
+    }
+}
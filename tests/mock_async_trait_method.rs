@@ -0,0 +1,118 @@
+/* Copyright 2017 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+#![feature(proc_macro)]
+extern crate galvanic_mock;
+extern crate galvanic_assert;
+use galvanic_mock::{mockable, use_mocks};
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll, Waker};
+
+#[mockable]
+trait AsyncTrait {
+    fn fetch(&self, id: u32) -> impl Future<Output = String>;
+    fn boxed(&self, x: i32) -> Box<dyn Future<Output = i32>>;
+    fn pinned(&self, x: i32) -> Pin<Box<dyn Future<Output = i32>>>;
+    fn sendable(&self, x: i32) -> Pin<Box<dyn Future<Output = i32> + Send>>;
+}
+
+fn poll_once<F: Future + Unpin>(future: &mut F) -> Poll<F::Output> {
+    Pin::new(future).poll(&mut Context::from_waker(Waker::noop()))
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = Box::pin(future);
+    match poll_once(&mut future) {
+        Poll::Ready(value) => value,
+        Poll::Pending => panic!("future is not ready")
+    }
+}
+
+#[test]
+#[use_mocks]
+fn return_ready_future_for_impl_future() {
+    let mock = new_mock!(AsyncTrait);
+
+    given! {
+        <mock as AsyncTrait>::fetch(|&id| id == 1) then_return "one".to_string() always;
+        <mock as AsyncTrait>::fetch(|_| true) then_return_from |&(id,)| format!("id {}", id) always;
+    }
+
+    assert_eq!(block_on(mock.fetch(1)), "one".to_string());
+    assert_eq!(block_on(mock.fetch(2)), "id 2".to_string());
+}
+
+#[test]
+#[use_mocks]
+fn return_ready_future_for_boxed_futures() {
+    let mock = new_mock!(AsyncTrait);
+
+    given! {
+        <mock as AsyncTrait>::boxed(|_| true) then_return 12 always;
+        <mock as AsyncTrait>::pinned |&(x,)| x > 0 then_return_from |&(x,)| x * 2 always;
+    }
+
+    assert_eq!(block_on(Pin::from(mock.boxed(1))), 12);
+    assert_eq!(block_on(mock.pinned(2)), 4);
+}
+
+#[test]
+#[use_mocks]
+fn return_pending_future_until_triggered() {
+    let mock = new_mock!(AsyncTrait);
+    let trigger = mock::FutureTrigger::new();
+
+    given! {
+        bind trigger: mock::FutureTrigger = trigger.clone();
+        <mock as AsyncTrait>::pinned(|_| true) then_return 2 after_trigger bound.trigger always;
+    }
+
+    let mut future = mock.pinned(1);
+    assert_eq!(poll_once(&mut future), Poll::Pending);
+    trigger.fire();
+    assert_eq!(poll_once(&mut future), Poll::Ready(2));
+}
+
+#[test]
+#[use_mocks]
+fn return_sendable_future_for_pinned_send_futures() {
+    let mock = new_mock!(AsyncTrait);
+    let trigger = mock::FutureTrigger::new();
+
+    given! {
+        bind trigger: mock::FutureTrigger = trigger.clone();
+        <mock as AsyncTrait>::sendable(|_| true) then_return 3 after_trigger bound.trigger always;
+    }
+
+    let future = mock.sendable(1);
+    let handle = std::thread::spawn(move || {
+        let mut future = future;
+        while poll_once(&mut future) == Poll::Pending {
+            std::thread::yield_now();
+        }
+    });
+    trigger.fire();
+    handle.join().unwrap();
+}
+
+#[test]
+#[should_panic]
+#[use_mocks]
+fn calling_async_method_without_behaviour_panics() {
+    let mock = new_mock!(AsyncTrait);
+
+    mock.fetch(1);
+}
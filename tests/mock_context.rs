@@ -0,0 +1,154 @@
+/* Copyright 2017 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+#![feature(proc_macro)]
+extern crate galvanic_mock;
+extern crate galvanic_assert;
+use galvanic_mock::{mockable, use_mocks};
+
+#[mockable]
+trait TestTrait {
+    fn func(&self, x: i32) -> i32;
+}
+
+#[test]
+#[use_mocks]
+fn verify_registered_mocks_together() {
+    let context = mock::MockContext::new();
+    let first = new_mock!(TestTrait for First);
+    let second = new_mock!(TestTrait for Second);
+
+    given! {
+        <first as TestTrait>::func |_| true then_return 1 always;
+        <second as TestTrait>::func |_| true then_return 2 always;
+    }
+
+    expect_interactions! {
+        <first as TestTrait>::func(|&a| a == 1) times 1;
+        <second as TestTrait>::func(|&a| a == 2) times 1;
+    }
+
+    assert_eq!(first.func(1), 1);
+    assert!(!context.verification_report().is_satisfied());
+    assert_eq!(second.func(2), 2);
+
+    let report = context.verification_report();
+    assert!(report.is_satisfied());
+    assert_eq!(report.expectations.iter().map(|expectation| expectation.mock).collect::<Vec<_>>(),
+               vec!["First", "Second"]);
+}
+
+#[test]
+#[should_panic(expected = "There are unsatisfied expected behaviours for mocked traits:\nFirst:\n\tBehaviour unsatisfied: TestTrait::func(| & a | a == 1) times 1")]
+#[use_mocks]
+fn verify_dropped_mocks_when_context_is_dropped() {
+    let _context = mock::MockContext::new();
+    {
+        let first = new_mock!(TestTrait for First);
+
+        expect_interactions! {
+            <first as TestTrait>::func(|&a| a == 1) times 1;
+        }
+    }
+}
+
+#[test]
+#[should_panic(expected = "There are unsatisfied expected behaviours for mocked traits:\n\tBehaviour unsatisfied: TestTrait::func(| & a | a == 1) times 1")]
+#[use_mocks]
+fn verify_dropped_mocks_on_drop_if_context_does_not_verify_on_drop() {
+    let mut context = mock::MockContext::new();
+    context.should_verify_on_drop(false);
+    {
+        let first = new_mock!(TestTrait for First);
+
+        expect_interactions! {
+            <first as TestTrait>::func(|&a| a == 1) times 1;
+        }
+    }
+}
+
+#[test]
+#[use_mocks]
+fn record_calls_of_all_mocks_in_order() {
+    let mut context = mock::MockContext::new();
+    let first = new_mock!(TestTrait for First);
+    let second = new_mock!(TestTrait for Second);
+
+    given! {
+        <first as TestTrait>::func |_| true then_return 1 always;
+        <second as TestTrait>::func |_| true then_return 2 always;
+    }
+
+    first.func(1);
+    second.func(2);
+    first.func(3);
+
+    let calls = context.call_history().into_iter()
+                       .map(|call| format!("{}.{}({})", call.mock, call.method, call.args.join(", ")))
+                       .collect::<Vec<_>>();
+    assert_eq!(calls, vec!["First.func(1)", "Second.func(2)", "First.func(3)"]);
+    context.should_verify_on_drop(false);
+}
+
+#[test]
+#[use_mocks]
+fn mocks_created_outside_of_context_are_not_registered() {
+    let mut mock = new_mock!(TestTrait);
+    let context = mock::MockContext::new();
+
+    expect_interactions! {
+        <mock as TestTrait>::func(|_| true) times 1;
+    }
+
+    assert!(context.verification_report().expectations.is_empty());
+    mock.should_verify_on_drop(false);
+}
+
+#[test]
+#[should_panic(expected = "There are unsatisfied expected behaviours for mocked traits:\n\tBehaviour unsatisfied: TestTrait::func(| & a | a == 1) times 1")]
+#[use_mocks]
+fn verify_mocks_outliving_their_context_on_drop() {
+    let mock;
+    {
+        let mut context = mock::MockContext::new();
+        mock = new_mock!(TestTrait);
+        context.should_verify_on_drop(false);
+    }
+
+    expect_interactions! {
+        <mock as TestTrait>::func(|&a| a == 1) times 1;
+    }
+}
+
+#[test]
+#[use_mocks]
+fn do_not_verify_mocks_outliving_their_context_again() {
+    let mock;
+    {
+        let _context = mock::MockContext::new();
+        mock = new_mock!(TestTrait);
+
+        given! {
+            <mock as TestTrait>::func |_| true then_return 1 always;
+        }
+
+        expect_interactions! {
+            <mock as TestTrait>::func(|&a| a == 1) times 1;
+        }
+
+        mock.func(1);
+    }
+
+    mock.func(1);
+}
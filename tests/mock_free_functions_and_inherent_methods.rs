@@ -0,0 +1,159 @@
+/* Copyright 2017 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+#![feature(proc_macro)]
+extern crate galvanic_mock;
+extern crate galvanic_assert;
+use galvanic_mock::{mockable, use_mocks};
+
+use std::rc::Rc;
+
+#[mockable]
+fn read_config(path: &str) -> String {
+    format!("real config from {}", path)
+}
+
+#[mockable]
+fn sum((a, b): (i32, i32), &c: &i32) -> i32 {
+    a + b + c
+}
+
+pub struct Client {
+    prefix: String
+}
+
+#[mockable]
+impl Client {
+    pub fn new(prefix: &str) -> Self {
+        Client { prefix: prefix.to_string() }
+    }
+
+    pub fn get(&self, key: &str) -> String {
+        format!("{}{}", self.prefix, key)
+    }
+
+    pub fn set(&mut self, prefix: String) {
+        self.prefix = prefix;
+    }
+}
+
+#[test]
+fn call_real_function_without_installed_mock() {
+    assert_eq!(read_config("a"), "real config from a".to_string());
+    assert_eq!(Client::new("x").get("a"), "xa".to_string());
+}
+
+#[test]
+#[use_mocks]
+fn mock_free_function() {
+    let mock = new_mock!(MockableReadConfig);
+    let mock = Rc::new(mock);
+    let _installation = MockableReadConfig::install_mock(mock.clone());
+
+    given! {
+        <mock as MockableReadConfig>::read_config(|&path| path == "a") then_return "mocked config".to_string() always;
+    }
+    expect_interactions! {
+        <mock as MockableReadConfig>::read_config(|_| true) times 1;
+    }
+
+    assert_eq!(read_config("a"), "mocked config".to_string());
+}
+
+#[test]
+#[use_mocks]
+fn mock_inherent_methods() {
+    let mock = new_mock!(MockableClient);
+    let mock = Rc::new(mock);
+    let _installation = MockableClient::install_mock(mock.clone());
+
+    given! {
+        <mock as MockableClient>::{
+            new(|_| true) then_return Client { prefix: "mocked ".to_string() } always;
+            get(|&key| key == "a") then_return "mocked a".to_string() always;
+            set(|_| true) then_return () always;
+        };
+    }
+    expect_interactions! {
+        <mock as MockableClient>::set(|prefix| prefix == "y") times 1;
+    }
+
+    let mut client = Client::new("x");
+    assert_eq!(client.prefix, "mocked ".to_string());
+    assert_eq!(client.get("a"), "mocked a".to_string());
+    client.set("y".to_string());
+    assert_eq!(client.prefix, "mocked ".to_string());
+}
+
+#[test]
+#[use_mocks]
+fn call_real_function_after_mock_is_dropped() {
+    let _installation;
+    {
+        let mock = new_mock!(MockableReadConfig);
+        let mock = Rc::new(mock);
+        _installation = MockableReadConfig::install_mock(mock.clone());
+    }
+
+    assert_eq!(read_config("a"), "real config from a".to_string());
+}
+
+#[test]
+#[use_mocks]
+fn call_real_function_after_installation_is_dropped() {
+    let mock = new_mock!(MockableReadConfig);
+    let mock = Rc::new(mock);
+    {
+        let _installation = MockableReadConfig::install_mock(mock.clone());
+    }
+
+    assert_eq!(read_config("a"), "real config from a".to_string());
+}
+
+#[test]
+#[use_mocks]
+fn restore_previous_mock_when_installation_is_dropped() {
+    let outer = new_mock!(MockableReadConfig);
+    let outer = Rc::new(outer);
+    let _outer_installation = MockableReadConfig::install_mock(outer.clone());
+    {
+        let inner = new_mock!(MockableReadConfig);
+        let inner = Rc::new(inner);
+        let _inner_installation = MockableReadConfig::install_mock(inner.clone());
+        given! {
+            <inner as MockableReadConfig>::read_config |_| true then_return "inner".to_string() always;
+        }
+        assert_eq!(read_config("a"), "inner".to_string());
+    }
+
+    given! {
+        <outer as MockableReadConfig>::read_config |_| true then_return "outer".to_string() always;
+    }
+    assert_eq!(read_config("a"), "outer".to_string());
+}
+
+#[test]
+#[use_mocks]
+fn mock_functions_with_destructured_arguments() {
+    assert_eq!(sum((1, 2), &3), 6);
+
+    let mock = new_mock!(MockableSum);
+    let mock = Rc::new(mock);
+    let _installation = MockableSum::install_mock(mock.clone());
+    given! {
+        <mock as MockableSum>::sum(|&(a, b)| a == 1 && b == 2, |&&c| c == 3) then_return 0 always;
+    }
+
+    assert_eq!(sum((1, 2), &3), 0);
+}
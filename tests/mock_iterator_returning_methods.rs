@@ -0,0 +1,86 @@
+/* Copyright 2017 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+#![feature(proc_macro)]
+extern crate galvanic_mock;
+extern crate galvanic_assert;
+use galvanic_mock::{mockable, use_mocks};
+
+use std::fmt::Display;
+
+#[mockable]
+trait Repository {
+    fn ids(&self, x: i32) -> impl Iterator<Item = i32>;
+    fn names(&self) -> Box<Iterator<Item = String>>;
+    fn values(&self) -> std::vec::IntoIter<i32>;
+}
+
+#[mockable]
+trait Labeled {
+    fn label(&self) -> impl Display;
+}
+
+#[test]
+#[use_mocks]
+fn return_iter_for_impl_iterator() {
+    let mock = new_mock!(Repository);
+
+    given! {
+        <mock as Repository>::ids(|&x| x == 1) then_return_iter vec![1, 2, 3] always;
+        <mock as Repository>::ids(|&x| x == 2) then_return (1..3).map(|x| x * 2) always;
+        <mock as Repository>::ids(|_| true) then_return_from |&(x,)| std::iter::repeat(x).take(2) always;
+    }
+
+    assert_eq!(mock.ids(1).collect::<Vec<_>>(), vec![1, 2, 3]);
+    assert_eq!(mock.ids(2).collect::<Vec<_>>(), vec![2, 4]);
+    assert_eq!(mock.ids(3).collect::<Vec<_>>(), vec![3, 3]);
+}
+
+#[test]
+#[use_mocks]
+fn return_iter_for_boxed_iterator() {
+    let mock = new_mock!(Repository);
+
+    given! {
+        <mock as Repository>::names() then_return_iter vec!["a".to_string(), "b".to_string()] times 1;
+        <mock as Repository>::names() then_return Box::new(None.into_iter()) always;
+    }
+
+    assert_eq!(mock.names().collect::<Vec<_>>(), vec!["a".to_string(), "b".to_string()]);
+    assert_eq!(mock.names().count(), 0);
+}
+
+#[test]
+#[use_mocks]
+fn return_iter_for_concrete_iterator() {
+    let mock = new_mock!(Repository);
+
+    given! {
+        <mock as Repository>::values() then_return_iter vec![1, 2] always;
+    }
+
+    assert_eq!(mock.values().collect::<Vec<_>>(), vec![1, 2]);
+}
+
+#[test]
+#[use_mocks]
+fn return_value_for_impl_trait() {
+    let mock = new_mock!(Labeled);
+
+    given! {
+        <mock as Labeled>::label() then_return 12 always;
+    }
+
+    assert_eq!(mock.label().to_string(), "12".to_string());
+}
@@ -0,0 +1,106 @@
+/* Copyright 2017 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+#![feature(proc_macro)]
+extern crate galvanic_mock;
+extern crate galvanic_assert;
+use galvanic_mock::{mockable, use_mocks};
+
+use std::pin::Pin;
+use std::rc::Rc;
+
+#[mockable]
+trait TestTrait {
+    fn finish(self, x: i32) -> i32;
+    fn into_inner(self: Box<Self>, x: i32) -> i32;
+    fn shared(self: Rc<Self>, x: i32) -> i32;
+    fn pinned(self: Pin<&mut Self>, x: i32) -> i32;
+}
+
+#[test]
+#[use_mocks]
+fn call_method_consuming_self() {
+    let mock = new_mock!(TestTrait);
+
+    given! {
+        <mock as TestTrait>::finish(|&x| x == 2) then_return 12 always;
+    }
+    expect_interactions! {
+        <mock as TestTrait>::finish(|&x| x == 2) times 1;
+    }
+
+    assert_eq!(mock.finish(2), 12);
+}
+
+#[test]
+#[should_panic]
+#[use_mocks]
+fn verify_when_consumed_mock_is_dropped_in_method() {
+    let mock = new_mock!(TestTrait);
+
+    given! {
+        <mock as TestTrait>::finish(|_| true) then_return 12 always;
+    }
+    expect_interactions! {
+        <mock as TestTrait>::finish(|_| true) times 2;
+    }
+
+    mock.finish(2);
+}
+
+#[test]
+#[use_mocks]
+fn call_method_with_boxed_self() {
+    let mock = new_mock!(TestTrait);
+
+    given! {
+        <mock as TestTrait>::into_inner(|&x| x == 2) then_return 12 always;
+    }
+    expect_interactions! {
+        <mock as TestTrait>::into_inner(|&x| x == 2) times 1;
+    }
+
+    let boxed: Box<TestTrait> = Box::new(mock);
+    assert_eq!(boxed.into_inner(2), 12);
+}
+
+#[test]
+#[use_mocks]
+fn call_method_with_rc_self() {
+    let mock = new_mock!(TestTrait);
+
+    given! {
+        <mock as TestTrait>::shared(|&x| x == 2) then_return 12 always;
+    }
+    expect_interactions! {
+        <mock as TestTrait>::shared(|&x| x == 2) times 2;
+    }
+
+    let shared = Rc::new(mock);
+    assert_eq!(shared.clone().shared(2), 12);
+    assert_eq!(shared.shared(2), 12);
+}
+
+#[test]
+#[use_mocks]
+fn call_method_with_pinned_self() {
+    let mut mock = new_mock!(TestTrait);
+
+    given! {
+        <mock as TestTrait>::pinned(|&x| x == 2) then_return 12 always;
+    }
+
+    assert_eq!(Pin::new(&mut mock).pinned(2), 12);
+    assert_eq!(Pin::new(&mut mock).pinned(2), 12);
+}
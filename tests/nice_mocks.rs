@@ -0,0 +1,86 @@
+/* Copyright 2017 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+#![feature(proc_macro)]
+extern crate galvanic_mock;
+extern crate galvanic_assert;
+use galvanic_mock::{mockable, use_mocks};
+
+#[derive(Debug, PartialEq)]
+pub struct NoDefault(i32);
+
+#[mockable]
+trait TestTrait {
+    fn number(&self, x: i32) -> i32;
+    fn text(&self) -> String;
+    fn maybe(&self) -> Option<u8>;
+    fn list(&self) -> Vec<i32>;
+    fn result(&self) -> Result<u32, String>;
+    fn nothing(&self, x: i32);
+    fn items(&self) -> impl Iterator<Item = i32>;
+    fn no_default(&self) -> NoDefault;
+}
+
+#[test]
+#[use_mocks]
+fn return_default_values_for_unstubbed_methods() {
+    let mock = new_mock!(TestTrait #[nice]);
+
+    assert_eq!(mock.number(1), 0);
+    assert_eq!(mock.text(), String::new());
+    assert_eq!(mock.maybe(), None);
+    assert_eq!(mock.list(), Vec::<i32>::new());
+    assert_eq!(mock.result(), Ok(0));
+    mock.nothing(1);
+    assert_eq!(mock.items().count(), 0);
+}
+
+#[test]
+#[use_mocks]
+fn prefer_given_behaviours_over_default_values() {
+    let mock = new_mock!(TestTrait #[nice]);
+
+    given! {
+        <mock as TestTrait>::number(|&x| x > 0) then_return 12 always;
+    }
+
+    assert_eq!(mock.number(1), 12);
+    assert_eq!(mock.number(-1), 0);
+}
+
+#[test]
+#[use_mocks]
+fn combine_nice_with_other_attributes() {
+    let mock = new_mock!(TestTrait #[nice] #[allow(dead_code)]);
+
+    assert_eq!(mock.number(1), 0);
+}
+
+#[test]
+#[should_panic(expected = "No matching given! statement found for call no_default()")]
+#[use_mocks]
+fn panic_if_return_type_has_no_default_value() {
+    let mock = new_mock!(TestTrait #[nice]);
+
+    mock.no_default();
+}
+
+#[test]
+#[should_panic(expected = "No matching given! statement found for call number(1)")]
+#[use_mocks]
+fn panic_for_unstubbed_methods_of_regular_mocks() {
+    let mock = new_mock!(TestTrait);
+
+    mock.number(1);
+}
@@ -0,0 +1,88 @@
+/* Copyright 2017 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+#![cfg(not(feature = "galvanic_assert_integration"))]
+#![feature(proc_macro)]
+extern crate galvanic_mock;
+extern crate galvanic_assert;
+use galvanic_mock::{mockable, use_mocks};
+
+use std::panic::{self, AssertUnwindSafe};
+
+#[derive(Debug, PartialEq)]
+pub struct JobFailure {
+    job: i32,
+    reason: &'static str
+}
+
+#[mockable]
+trait Job {
+    fn run(&self, id: i32) -> i32;
+    fn cleanup(&self);
+}
+
+#[test]
+#[should_panic(expected = "worker crashed")]
+#[use_mocks]
+fn panic_with_message() {
+    let mock = new_mock!(Job);
+
+    given! {
+        <mock as Job>::run(any()) then_panic_with "worker crashed" always;
+    }
+
+    mock.run(1);
+}
+
+#[test]
+#[should_panic(expected = "job 7 failed")]
+#[use_mocks]
+fn panic_with_formatted_message() {
+    let mock = new_mock!(Job);
+    let job = 7;
+
+    given! {
+        <mock as Job>::cleanup() then_panic_with format!("job {} failed", job) always;
+    }
+
+    mock.cleanup();
+}
+
+#[test]
+#[use_mocks]
+fn panic_with_payload_is_caught() {
+    let mock = new_mock!(Job);
+
+    given! {
+        <mock as Job>::run(eq(1)) then_return 10 always;
+        <mock as Job>::run(eq(2)) then_panic_with_payload JobFailure { job: 2, reason: "out of memory" } always;
+    }
+
+    assert_eq!(mock.run(1), 10);
+    let payload = panic::catch_unwind(AssertUnwindSafe(|| mock.run(2))).unwrap_err();
+    assert_eq!(payload.downcast_ref::<JobFailure>(), Some(&JobFailure { job: 2, reason: "out of memory" }));
+}
+
+#[test]
+#[should_panic(expected = "Don't forget the towel")]
+#[use_mocks]
+fn panic_without_message() {
+    let mock = new_mock!(Job);
+
+    given! {
+        <mock as Job>::run(any()) then_panic always;
+    }
+
+    mock.run(1);
+}
@@ -0,0 +1,130 @@
+/* Copyright 2017 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+#![cfg(not(feature = "galvanic_assert_integration"))]
+#![feature(proc_macro)]
+extern crate galvanic_mock;
+extern crate galvanic_assert;
+use galvanic_mock::{mockable, use_mocks};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    Click { x: i32, y: i32 },
+    Key(char),
+    Text(String),
+    Close
+}
+
+#[mockable]
+trait Handler {
+    fn handle(&self, event: Event) -> i32;
+    fn handle_maybe(&self, event: Option<Event>, repeat: u32) -> String;
+}
+
+#[test]
+#[use_mocks]
+fn match_arguments_with_patterns() {
+    let mock = new_mock!(Handler);
+
+    given! {
+        <mock as Handler>::handle(pat!(Event::Click { x, .. } if x > 10)) then_return 1 always;
+        <mock as Handler>::handle(pat!(Event::Key('q') | Event::Close)) then_return 2 always;
+        <mock as Handler>::handle(pat!(Event::Text(ref text) if text.starts_with("cmd:"))) then_return 3 always;
+        <mock as Handler>::handle(any()) then_return 4 always;
+    }
+
+    assert_eq!(mock.handle(Event::Click { x: 11, y: 0 }), 1);
+    assert_eq!(mock.handle(Event::Click { x: 10, y: 0 }), 4);
+    assert_eq!(mock.handle(Event::Key('q')), 2);
+    assert_eq!(mock.handle(Event::Close), 2);
+    assert_eq!(mock.handle(Event::Text("cmd:quit".to_string())), 3);
+    assert_eq!(mock.handle(Event::Text("hello".to_string())), 4);
+}
+
+#[test]
+#[use_mocks]
+fn use_pattern_bindings_in_return_values() {
+    let mock = new_mock!(Handler);
+
+    given! {
+        <mock as Handler>::handle(pat!(Event::Click { x, y })) then_return_from |_| x * y always;
+        <mock as Handler>::handle_maybe(pat!(Some(Event::Text(ref text))), pat!(n if n > 0)) then_return_from |_| text.repeat(n as usize) always;
+        <mock as Handler>::handle_maybe(pat!(_), any()) then_return_from |&(_, n)| format!("{}", n) always;
+    }
+
+    assert_eq!(mock.handle(Event::Click { x: 3, y: 4 }), 12);
+    assert_eq!(mock.handle_maybe(Some(Event::Text("ab".to_string())), 2), "abab");
+    assert_eq!(mock.handle_maybe(Some(Event::Close), 2), "2");
+    assert_eq!(mock.handle_maybe(Some(Event::Text("ab".to_string())), 0), "0");
+}
+
+#[test]
+#[use_mocks]
+fn use_patterns_in_expectations() {
+    let mock = new_mock!(Handler);
+
+    given! {
+        <mock as Handler>::handle(any()) then_return 1 always;
+    }
+
+    expect_interactions! {
+        <mock as Handler>::handle(pat!(Event::Key(c) if c.is_uppercase())) times 2;
+    }
+
+    mock.handle(Event::Key('A'));
+    mock.handle(Event::Key('b'));
+    mock.handle(Event::Key('C'));
+}
+
+#[test]
+#[should_panic(expected = "Handler::handle(matching Event :: Click { x , .. } if x > 10) then_return 1 always (unmatched arguments: #0)")]
+#[use_mocks]
+fn describe_patterns_in_failure_messages() {
+    let mock = new_mock!(Handler);
+
+    given! {
+        <mock as Handler>::handle(pat!(Event::Click { x, .. } if x > 10)) then_return 1 always;
+    }
+
+    mock.handle(Event::Close);
+}
+
+#[test]
+#[use_mocks]
+fn evaluate_guards_once_per_call() {
+    let mock = new_mock!(Handler);
+    let guard_calls = std::rc::Rc::new(std::cell::Cell::new(0));
+
+    given! {
+        bind guard_calls: std::rc::Rc<std::cell::Cell<i32>> = guard_calls.clone();
+        <mock as Handler>::handle(pat!(Event::Click { x, y } if { bound.guard_calls.set(bound.guard_calls.get() + 1); x > 0 })) then_return_from |_| x + y always;
+    }
+
+    assert_eq!(mock.handle(Event::Click { x: 1, y: 2 }), 3);
+    assert_eq!(guard_calls.get(), 1);
+}
+
+#[test]
+#[use_mocks]
+fn do_not_mistake_other_tokens_for_patterns() {
+    let mock = new_mock!(Handler);
+
+    given! {
+        <mock as Handler>::handle(eq(Event::Text(String::from("pat !")))) then_return 1 always;
+        <mock as Handler>::handle(any()) then_return 2 always;
+    }
+
+    assert_eq!(mock.handle(Event::Text(String::from("pat !"))), 1);
+    assert_eq!(mock.handle(Event::Close), 2);
+}
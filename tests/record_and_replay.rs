@@ -0,0 +1,148 @@
+/* Copyright 2017 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+#![cfg(not(feature = "galvanic_assert_integration"))]
+#![feature(proc_macro)]
+extern crate galvanic_mock;
+extern crate galvanic_assert;
+#[macro_use] extern crate serde_derive;
+extern crate serde_json;
+use galvanic_mock::{mockable, use_mocks};
+
+use std::cell::Cell;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Forecast {
+    day: u32,
+    temperature: i32
+}
+
+#[mockable]
+trait Weather {
+    fn forecast(&self, city: &str, days: u32) -> Vec<Forecast>;
+    fn station_count(&self) -> usize;
+}
+
+struct WeatherService {
+    num_requests: Cell<usize>
+}
+
+impl WeatherService {
+    fn new() -> Self {
+        WeatherService { num_requests: Cell::new(0) }
+    }
+}
+
+impl Weather for WeatherService {
+    fn forecast(&self, city: &str, days: u32) -> Vec<Forecast> {
+        self.num_requests.set(self.num_requests.get() + 1);
+        (1..days + 1).map(|day| Forecast { day, temperature: city.len() as i32 + day as i32 }).collect()
+    }
+
+    fn station_count(&self) -> usize {
+        self.num_requests.set(self.num_requests.get() + 1);
+        self.num_requests.get()
+    }
+}
+
+#[test]
+#[use_mocks]
+fn spy_forwards_calls_without_given_behaviour() {
+    let mock = new_mock!(Weather #[spy_on(WeatherService)]);
+    mock.spy_on(WeatherService::new());
+
+    given! {
+        <mock as Weather>::station_count() then_return 42 times 1;
+    }
+    expect_interactions! {
+        <mock as Weather>::forecast(eq("Graz"), any()) times 1;
+    }
+
+    assert_eq!(mock.station_count(), 42);
+    assert_eq!(mock.forecast("Graz", 1), vec![Forecast { day: 1, temperature: 5 }]);
+    assert_eq!(mock.station_count(), 2);
+    mock.verify();
+}
+
+#[test]
+#[use_mocks]
+fn then_spy_on_object_forwards_matching_calls() {
+    let mock = new_mock!(Weather #[spy_on(WeatherService)]);
+    mock.spy_on(WeatherService::new());
+
+    given! {
+        <mock as Weather>::forecast(eq("Linz"), any()) then_spy_on_object always;
+        <mock as Weather>::forecast(any(), any()) then_return Vec::new() always;
+    }
+
+    assert_eq!(mock.forecast("Linz", 2), vec![Forecast { day: 1, temperature: 5 }, Forecast { day: 2, temperature: 6 }]);
+    assert_eq!(mock.forecast("Graz", 2), Vec::new());
+}
+
+#[test]
+#[use_mocks]
+fn record_calls_to_fixture() {
+    let path = std::env::temp_dir().join(format!("galvanic_mock_recorded_weather_{}.json", std::process::id()));
+    let mock = new_mock!(Weather #[spy_on(WeatherService)] #[record_to("tests/fixtures/recorded_weather.json")]);
+    mock.spy_on(WeatherService::new());
+    mock.record_to(&path);
+
+    mock.forecast("Wien", 1);
+    mock.station_count();
+    mock.save_recorded_calls();
+
+    let fixture = std::fs::read_to_string(&path).unwrap();
+    drop(mock);
+    std::fs::remove_file(&path).unwrap();
+    let calls: serde_json::Value = serde_json::from_str(&fixture).unwrap();
+    assert_eq!(calls[0]["trait"], "Weather");
+    assert_eq!(calls[0]["method"], "forecast");
+    assert_eq!(calls[0]["args"], serde_json::Value::Array(vec!["Wien".into(), 1.into()]));
+    assert_eq!(calls[0]["returns"][0]["temperature"], 5);
+    assert_eq!(calls[1]["method"], "station_count");
+    assert_eq!(calls[1]["returns"], 2);
+}
+
+#[test]
+#[use_mocks]
+fn replay_calls_from_fixture() {
+    let mock = new_mock!(Weather #[replay_from("tests/fixtures/weather.json")]);
+
+    assert_eq!(mock.forecast("Vienna", 2), vec![Forecast { day: 1, temperature: 21 }, Forecast { day: 2, temperature: 19 }]);
+    assert_eq!(mock.station_count(), 3);
+    assert_eq!(mock.station_count(), 4);
+    assert_eq!(mock.station_count(), 4);
+}
+
+#[test]
+#[use_mocks]
+fn given_behaviour_overrides_replayed_call() {
+    let mock = new_mock!(Weather #[replay_from("tests/fixtures/weather.json")]);
+
+    given! {
+        <mock as Weather>::station_count() then_return 7 times 1;
+    }
+
+    assert_eq!(mock.station_count(), 7);
+    assert_eq!(mock.station_count(), 3);
+}
+
+#[test]
+#[should_panic]
+#[use_mocks]
+fn unrecorded_call_panics_on_replay() {
+    let mock = new_mock!(Weather #[replay_from("tests/fixtures/weather.json")]);
+
+    mock.forecast("Salzburg", 2);
+}
@@ -0,0 +1,153 @@
+/* Copyright 2017 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+#![feature(proc_macro)]
+extern crate galvanic_mock;
+extern crate galvanic_assert;
+use galvanic_mock::{mockable, use_mocks};
+
+#[mockable]
+trait TestTrait {
+    fn func(&self, x: i32) -> i32;
+}
+
+#[mockable]
+trait OtherTrait {
+    fn other(&self) -> i32;
+}
+
+#[test]
+#[use_mocks]
+fn scoped_given_behaviours_are_removed_at_end_of_scope() {
+    let mock = new_mock!(TestTrait);
+
+    given! {
+        <mock as TestTrait>::func(|&x| x == 1) then_return 1 always;
+    }
+
+    {
+        let _guard = given_scoped! {
+            <mock as TestTrait>::func |_| true then_return 2 always;
+        };
+        assert_eq!(mock.func(1), 1);
+        assert_eq!(mock.func(2), 2);
+    }
+
+    given! {
+        <mock as TestTrait>::func |_| true then_return 3 always;
+    }
+    assert_eq!(mock.func(1), 1);
+    assert_eq!(mock.func(2), 3);
+}
+
+#[test]
+#[use_mocks]
+fn scoped_given_behaviours_are_added_after_existing_ones() {
+    let mock = new_mock!(TestTrait);
+
+    given! {
+        <mock as TestTrait>::func |_| true then_return 1 times 1;
+    }
+
+    let _guard = given_scoped! {
+        <mock as TestTrait>::func |_| true then_return 2 always;
+    };
+    assert_eq!(mock.func(1), 1);
+    assert_eq!(mock.func(1), 2);
+}
+
+#[test]
+#[use_mocks]
+fn scoped_given_behaviours_for_multiple_mocks() {
+    let mock = new_mock!(TestTrait);
+    let other_mock = new_mock!(OtherTrait);
+
+    {
+        let _guard = given_scoped! {
+            bind value: i32 = 2;
+            <mock as TestTrait>::func |_| true then_return bound.value always;
+            <other_mock as OtherTrait>::other() then_return bound.value always;
+        };
+        assert_eq!(mock.func(1), 2);
+        assert_eq!(other_mock.other(), 2);
+    }
+
+    given! {
+        <mock as TestTrait>::func |_| true then_return 1 always;
+        <other_mock as OtherTrait>::other() then_return 1 always;
+    }
+    assert_eq!(mock.func(1), 1);
+    assert_eq!(other_mock.other(), 1);
+}
+
+#[test]
+#[should_panic]
+#[use_mocks]
+fn scoped_given_behaviour_no_longer_matches_after_scope() {
+    let mock = new_mock!(TestTrait);
+
+    {
+        let _guard = given_scoped! {
+            <mock as TestTrait>::func |_| true then_return 2 always;
+        };
+    }
+
+    mock.func(1);
+}
+
+#[test]
+#[use_mocks]
+fn scoped_expectations_are_verified_and_removed_at_end_of_scope() {
+    let mock = new_mock!(TestTrait);
+
+    given! {
+        <mock as TestTrait>::func |_| true then_return 1 always;
+    }
+
+    {
+        let _guard = expect_interactions_scoped! {
+            <mock as TestTrait>::func(|&x| x == 1) times 1;
+        };
+        mock.func(1);
+    }
+
+    {
+        let _guard = expect_interactions_scoped! {
+            <mock as TestTrait>::func(|&x| x == 1) times 2;
+        };
+        mock.func(1);
+        mock.func(1);
+    }
+
+    mock.func(1);
+}
+
+#[test]
+#[should_panic(expected = "Behaviour unsatisfied")]
+#[use_mocks]
+fn unsatisfied_scoped_expectations_panic_at_end_of_scope() {
+    let mut mock = new_mock!(TestTrait);
+    mock.should_verify_on_drop(false);
+
+    given! {
+        <mock as TestTrait>::func |_| true then_return 1 always;
+    }
+
+    {
+        let _guard = expect_interactions_scoped! {
+            <mock as TestTrait>::func(|&x| x == 1) times 2;
+        };
+        mock.func(1);
+    }
+}
@@ -0,0 +1,85 @@
+/* Copyright 2017 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+#![feature(proc_macro)]
+extern crate galvanic_mock;
+extern crate galvanic_assert;
+use galvanic_mock::{mockable, use_mocks};
+
+#[mockable]
+trait TestTrait {
+    fn func(&self, x: i32, y: i32) -> i32;
+}
+
+#[test]
+#[should_panic(expected = "expected exactly 2 matching calls, but got 1")]
+#[use_mocks]
+fn report_expected_and_actual_number_of_matches() {
+    let mock = new_mock!(TestTrait);
+
+    given! {
+        <mock as TestTrait>::func |_| true then_return 12 always;
+    }
+
+    expect_interactions! {
+        <mock as TestTrait>::func(|&a| a < 2, |_| true) times(2);
+    }
+
+    mock.func(1, 2);
+}
+
+#[test]
+#[should_panic(expected = "calls of <TestTrait>::func:\n\t\t\tfunc(1, 2) matched")]
+#[use_mocks]
+fn report_calls_with_arguments() {
+    let mock = new_mock!(TestTrait);
+
+    given! {
+        <mock as TestTrait>::func |_| true then_return 12 always;
+    }
+
+    expect_interactions! {
+        <mock as TestTrait>::func(|&a| a < 2, |_| true) times(2);
+    }
+
+    mock.func(1, 2);
+}
+
+#[test]
+#[should_panic(expected = "func(5, 2) unmatched, nearest expectation: TestTrait::func(| & a | a < 2, | _ | true) times ( 2 ) (argument matcher #0 not satisfied)")]
+#[use_mocks]
+fn report_nearest_expectation_of_unmatched_calls() {
+    let mock = new_mock!(TestTrait);
+
+    given! {
+        <mock as TestTrait>::func |_| true then_return 12 always;
+    }
+
+    expect_interactions! {
+        <mock as TestTrait>::func(|&a| a < 2, |_| true) times(2);
+    }
+
+    mock.func(5, 2);
+}
+
+#[test]
+#[should_panic(expected = "no calls of <TestTrait>::func")]
+#[use_mocks]
+fn report_missing_calls() {
+    let mock = new_mock!(TestTrait);
+
+    expect_interactions! {
+        <mock as TestTrait>::func(|_| true, |_| true) at_least(1);
+    }
+}
@@ -0,0 +1,84 @@
+/* Copyright 2017 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+#![feature(proc_macro)]
+extern crate galvanic_mock;
+extern crate galvanic_assert;
+use galvanic_mock::{mockable, use_mocks};
+
+#[mockable]
+trait TestTrait {
+    fn func(&self, x: i32) -> i32;
+}
+
+#[test]
+#[use_mocks]
+fn report_state_of_expectations() {
+    let mut mock = new_mock!(TestTrait);
+
+    given! {
+        <mock as TestTrait>::func |_| true then_return 12 always;
+    }
+
+    expect_interactions! {
+        <mock as TestTrait>::func(|&a| a < 2) times(2);
+        <mock as TestTrait>::func(|&a| a == 5) at_most(1);
+        <mock as TestTrait>::func(|&a| a > 5) between 1, 3;
+    }
+
+    mock.func(1);
+    mock.func(5);
+    mock.func(5);
+
+    let report = mock.verification_report();
+    assert!(!report.is_satisfied());
+    assert_eq!(report.expectations.len(), 3);
+
+    let first = &report.expectations[0];
+    assert_eq!(first.requested_trait, "TestTrait");
+    assert_eq!(first.method, "func");
+    assert_eq!(first.statement, "TestTrait::func(| & a | a < 2) times ( 2 )");
+    assert_eq!((first.expected_min_matches, first.expected_max_matches), (Some(2), Some(2)));
+    assert_eq!(first.num_matches, 1);
+    assert_eq!(first.status, mock::ExpectationStatus::TooFewMatches);
+
+    assert_eq!(report.expectations[1].num_matches, 2);
+    assert_eq!(report.expectations[1].status, mock::ExpectationStatus::TooManyMatches);
+    assert_eq!(report.expectations[2].status, mock::ExpectationStatus::TooFewMatches);
+    assert_eq!(report.unsatisfied().len(), 3);
+
+    mock.should_verify_on_drop(false);
+}
+
+#[test]
+#[use_mocks]
+fn report_satisfied_expectations() {
+    let mock = new_mock!(TestTrait);
+
+    given! {
+        <mock as TestTrait>::func |_| true then_return 12 always;
+    }
+
+    expect_interactions! {
+        <mock as TestTrait>::func(|&a| a < 2) at_least(1);
+    }
+
+    mock.func(1);
+
+    let report = mock.verification_report();
+    assert!(report.is_satisfied());
+    assert!(report.unsatisfied().is_empty());
+    assert_eq!(report.expectations[0].status, mock::ExpectationStatus::Satisfied);
+    assert!(mock.are_expected_behaviours_satisfied());
+}